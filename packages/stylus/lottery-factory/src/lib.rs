@@ -0,0 +1,528 @@
+//!
+//! Lottery Factory Contract in Stylus Rust
+//!
+//! Deploys `Lottery` instances as EIP-1167 clones of a fixed implementation with
+//! CREATE2, so their addresses are known up front, initializes them in the same
+//! transaction and keeps an on-chain registry the frontend can list games from
+//! without hard-coded addresses.
+//!
+//! Clones can't be upgraded. `Lottery` keeps its UUPS `upgradeToAndCall`, but nothing
+//! writes the ERC-1967 implementation slot of a clone, so the call always reverts
+//! with `UUPSUnauthorizedCallContext` there. Games that must be upgradeable are
+//! deployed behind `UpgradeableProxy` instead.
+//!
+
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    alloy_primitives::{keccak256, Address, B256, U256},
+    alloy_sol_types::{sol, SolValue},
+    function_selector,
+    prelude::*,
+    stylus_core::calls::context::Call,
+    stylus_core::log,
+};
+
+use openzeppelin_stylus::access::ownable::{self, Ownable};
+
+// Define persistent storage
+sol_storage! {
+    #[entrypoint]
+    pub struct LotteryFactory {
+        address lottery_implementation;
+        address default_vrf_wrapper;
+
+        address[] lotteries;
+        mapping(address => LotteryInfo) registry;
+        address[] active_lotteries;
+        mapping(address => uint256) active_index;
+
+        Ownable ownable;
+    }
+
+    /// Registry entry for a deployed lottery
+    pub struct LotteryInfo {
+        address creator;
+        uint256 entry_fee;
+        address vrf_wrapper;
+        bytes32 salt;
+        uint256 created_at;
+        bool active;
+    }
+}
+
+// Events
+sol! {
+    event LotteryCreated(address indexed lottery, address indexed creator, bytes32 salt, uint256 entryFee, address vrfWrapper);
+    event LotteryDeactivated(address indexed lottery);
+    event DefaultVRFWrapperUpdated(address oldWrapper, address newWrapper);
+}
+
+// Errors
+sol! {
+    #[derive(Debug)]
+    error InvalidImplementation(address implementation);
+
+    #[derive(Debug)]
+    error DeploymentFailed(bytes32 salt);
+
+    #[derive(Debug)]
+    error InitializationFailed(address lottery);
+
+    #[derive(Debug)]
+    error LotteryNotFound(address lottery);
+
+    #[derive(Debug)]
+    error LotteryNotActive(address lottery);
+
+    #[derive(Debug)]
+    error NotLotteryCreator(address lottery, address caller);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Error {
+    InvalidImplementation(InvalidImplementation),
+    DeploymentFailed(DeploymentFailed),
+    InitializationFailed(InitializationFailed),
+    LotteryNotFound(LotteryNotFound),
+    LotteryNotActive(LotteryNotActive),
+    NotLotteryCreator(NotLotteryCreator),
+    UnauthorizedAccount(ownable::OwnableUnauthorizedAccount),
+    InvalidOwner(ownable::OwnableInvalidOwner),
+}
+
+impl From<ownable::Error> for Error {
+    fn from(value: ownable::Error) -> Self {
+        match value {
+            ownable::Error::UnauthorizedAccount(e) => Error::UnauthorizedAccount(e),
+            ownable::Error::InvalidOwner(e) => Error::InvalidOwner(e),
+        }
+    }
+}
+
+#[public]
+impl LotteryFactory {
+    /// Constructor - fixes the deployed `Lottery` every game is cloned from. It can't
+    /// be changed afterwards, so predicted addresses stay valid.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        lottery_implementation: Address,
+        default_vrf_wrapper: Address,
+        owner: Address,
+    ) -> Result<(), Error> {
+        if lottery_implementation.is_zero() {
+            return Err(Error::InvalidImplementation(InvalidImplementation {
+                implementation: lottery_implementation,
+            }));
+        }
+
+        self.ownable.constructor(owner)?;
+        self.lottery_implementation.set(lottery_implementation);
+        self.default_vrf_wrapper.set(default_vrf_wrapper);
+        Ok(())
+    }
+
+    /// Owner sets the VRF wrapper used when `create_lottery` is given the zero address
+    pub fn set_default_vrf_wrapper(&mut self, new_wrapper: Address) -> Result<(), Error> {
+        self.ownable.only_owner()?;
+        let old_wrapper = self.default_vrf_wrapper.get();
+        self.default_vrf_wrapper.set(new_wrapper);
+
+        log(
+            self.vm(),
+            DefaultVRFWrapperUpdated {
+                oldWrapper: old_wrapper,
+                newWrapper: new_wrapper,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Deploys a lottery owned by the caller. The CREATE2 salt is bound to the caller,
+    /// so `compute_lottery_address(caller, salt)` predicts the address and nobody else
+    /// can take it. Clones share the implementation's code and can't be upgraded, see
+    /// the module docs.
+    pub fn create_lottery(
+        &mut self,
+        salt: B256,
+        entry_fee: U256,
+        vrf_wrapper: Address,
+    ) -> Result<Address, Error> {
+        let init_code = clone_init_code(self.lottery_implementation.get());
+        let creator = self.vm().msg_sender();
+        let vrf_wrapper = self.vrf_wrapper_or_default(vrf_wrapper);
+
+        let lottery = unsafe {
+            self.vm()
+                .deploy(&init_code, U256::ZERO, Some(creator_salt(creator, salt)))
+        }
+        .map_err(|_| Error::DeploymentFailed(DeploymentFailed { salt }))?;
+
+        let mut calldata = function_selector!("initialize", Address, U256, Address).to_vec();
+        calldata.extend((vrf_wrapper, entry_fee, creator).abi_encode_params());
+        self.vm()
+            .call(&Call::new(), lottery, &calldata)
+            .map_err(|_| Error::InitializationFailed(InitializationFailed { lottery }))?;
+
+        self.register(lottery, creator, salt, entry_fee, vrf_wrapper);
+        Ok(lottery)
+    }
+
+    /// The creator or the factory owner removes a lottery from the active list
+    pub fn deactivate_lottery(&mut self, lottery: Address) -> Result<(), Error> {
+        let info = self.registry.get(lottery);
+        let creator = info.creator.get();
+        if creator.is_zero() {
+            return Err(Error::LotteryNotFound(LotteryNotFound { lottery }));
+        }
+        if !info.active.get() {
+            return Err(Error::LotteryNotActive(LotteryNotActive { lottery }));
+        }
+
+        let caller = self.vm().msg_sender();
+        if caller != creator && caller != self.ownable.owner() {
+            return Err(Error::NotLotteryCreator(NotLotteryCreator {
+                lottery,
+                caller,
+            }));
+        }
+
+        self.registry.setter(lottery).active.set(false);
+
+        // Swap-and-pop; `active_index` stores position + 1
+        let index: usize = (self.active_index.get(lottery) - U256::from(1))
+            .try_into()
+            .unwrap_or(0);
+        let last_index = self.active_lotteries.len() - 1;
+        if index != last_index {
+            let last = self.active_lotteries.get(last_index).unwrap_or_default();
+            if let Some(mut slot) = self.active_lotteries.setter(index) {
+                slot.set(last);
+            }
+            self.active_index.insert(last, U256::from(index + 1));
+        }
+        self.active_lotteries.pop();
+        self.active_index.insert(lottery, U256::ZERO);
+
+        log(self.vm(), LotteryDeactivated { lottery });
+
+        Ok(())
+    }
+
+    /// Address `create_lottery` will deploy to for `creator` and `salt`
+    pub fn compute_lottery_address(&self, creator: Address, salt: B256) -> Address {
+        let code_hash = keccak256(clone_init_code(self.lottery_implementation.get()));
+        self.vm()
+            .contract_address()
+            .create2(creator_salt(creator, salt), code_hash)
+    }
+
+    pub fn get_lottery_count(&self) -> U256 {
+        U256::from(self.lotteries.len())
+    }
+
+    pub fn get_lottery(&self, index: U256) -> Address {
+        self.lotteries
+            .get(index.try_into().unwrap_or(usize::MAX))
+            .unwrap_or_default()
+    }
+
+    /// Returns (creator, entry fee, VRF wrapper, created at, active)
+    pub fn get_lottery_info(&self, lottery: Address) -> (Address, U256, Address, U256, bool) {
+        let info = self.registry.get(lottery);
+        (
+            info.creator.get(),
+            info.entry_fee.get(),
+            info.vrf_wrapper.get(),
+            info.created_at.get(),
+            info.active.get(),
+        )
+    }
+
+    pub fn is_registered(&self, lottery: Address) -> bool {
+        !self.registry.get(lottery).creator.get().is_zero()
+    }
+
+    pub fn get_active_lottery_count(&self) -> U256 {
+        U256::from(self.active_lotteries.len())
+    }
+
+    pub fn get_active_lotteries(&self) -> Vec<Address> {
+        (0..self.active_lotteries.len())
+            .filter_map(|i| self.active_lotteries.get(i))
+            .collect()
+    }
+
+    pub fn get_lottery_implementation(&self) -> Address {
+        self.lottery_implementation.get()
+    }
+
+    /// Hash of the clone init code, for predicting addresses off-chain
+    pub fn get_lottery_init_code_hash(&self) -> B256 {
+        keccak256(clone_init_code(self.lottery_implementation.get()))
+    }
+
+    pub fn get_default_vrf_wrapper(&self) -> Address {
+        self.default_vrf_wrapper.get()
+    }
+
+    pub fn owner(&self) -> Address {
+        self.ownable.owner()
+    }
+}
+
+impl LotteryFactory {
+    /// `vrf_wrapper`, or the default wrapper for the zero address
+    fn vrf_wrapper_or_default(&self, vrf_wrapper: Address) -> Address {
+        if vrf_wrapper.is_zero() {
+            self.default_vrf_wrapper.get()
+        } else {
+            vrf_wrapper
+        }
+    }
+
+    /// Records a freshly deployed and initialized lottery as active
+    fn register(
+        &mut self,
+        lottery: Address,
+        creator: Address,
+        salt: B256,
+        entry_fee: U256,
+        vrf_wrapper: Address,
+    ) {
+        let created_at = U256::from(self.vm().block_timestamp());
+        let mut info = self.registry.setter(lottery);
+        info.creator.set(creator);
+        info.entry_fee.set(entry_fee);
+        info.vrf_wrapper.set(vrf_wrapper);
+        info.salt.set(salt);
+        info.created_at.set(created_at);
+        info.active.set(true);
+
+        self.lotteries.push(lottery);
+        self.active_lotteries.push(lottery);
+        self.active_index
+            .insert(lottery, U256::from(self.active_lotteries.len()));
+
+        log(
+            self.vm(),
+            LotteryCreated {
+                lottery,
+                creator,
+                salt,
+                entryFee: entry_fee,
+                vrfWrapper: vrf_wrapper,
+            },
+        );
+    }
+}
+
+/// CREATE2 salt scoped to the creator, so a salt can't be front-run by another account
+fn creator_salt(creator: Address, salt: B256) -> B256 {
+    keccak256([creator.as_slice(), salt.as_slice()].concat())
+}
+
+/// EIP-1167 minimal proxy init code delegating every call to `implementation`
+fn clone_init_code(implementation: Address) -> Vec<u8> {
+    let mut code = Vec::with_capacity(55);
+    code.extend_from_slice(&[
+        0x3d, 0x60, 0x2d, 0x80, 0x60, 0x0a, 0x3d, 0x39, 0x81, 0xf3, 0x36, 0x3d, 0x3d, 0x37, 0x3d,
+        0x3d, 0x3d, 0x36, 0x3d, 0x73,
+    ]);
+    code.extend_from_slice(implementation.as_slice());
+    code.extend_from_slice(&[
+        0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3,
+    ]);
+    code
+}
+
+/// motsu's CREATE2 deploys nothing, so `create_lottery` is covered through its parts:
+/// the address it predicts, the wrapper it picks and the registration it makes.
+#[cfg(test)]
+mod tests {
+    use motsu::prelude::Contract;
+    use stylus_sdk::alloy_primitives::{bytes, hex};
+
+    use super::*;
+
+    const IMPLEMENTATION: Address = Address::repeat_byte(0x11);
+    const WRAPPER: Address = Address::repeat_byte(0x22);
+    const ENTRY_FEE: U256 = U256::from_limbs([1_000, 0, 0, 0]);
+
+    fn deploy(factory: &Contract<LotteryFactory>, owner: Address) {
+        factory
+            .sender(owner)
+            .constructor(IMPLEMENTATION, WRAPPER, owner)
+            .unwrap();
+    }
+
+    /// Registers a lottery for `creator` the way a successful `create_lottery` does
+    fn register(factory: &Contract<LotteryFactory>, creator: Address, lottery: Address) {
+        factory.sender(creator).register(
+            lottery,
+            creator,
+            B256::repeat_byte(lottery[19]),
+            ENTRY_FEE,
+            WRAPPER,
+        );
+    }
+
+    #[motsu::test]
+    fn constructor_rejects_the_zero_implementation(
+        factory: Contract<LotteryFactory>,
+        alice: Address,
+    ) {
+        let err = factory
+            .sender(alice)
+            .constructor(Address::ZERO, WRAPPER, alice)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidImplementation(_)));
+    }
+
+    #[motsu::test]
+    fn compute_lottery_address_follows_create2(
+        factory: Contract<LotteryFactory>,
+        alice: Address,
+        bob: Address,
+    ) {
+        deploy(&factory, alice);
+        let salt = B256::repeat_byte(0x01);
+        let init_code = [
+            bytes!("3d602d80600a3d3981f3363d3d373d3d3d363d73").as_ref(),
+            IMPLEMENTATION.as_slice(),
+            bytes!("5af43d82803e903d91602b57fd5bf3").as_ref(),
+        ]
+        .concat();
+        let init_code_hash = keccak256(&init_code);
+        let preimage = [
+            hex!("ff").as_slice(),
+            factory.address().as_slice(),
+            keccak256([alice.as_slice(), salt.as_slice()].concat()).as_slice(),
+            init_code_hash.as_slice(),
+        ]
+        .concat();
+        let expected = Address::from_slice(&keccak256(preimage)[12..]);
+
+        assert_eq!(
+            factory.sender(alice).get_lottery_init_code_hash(),
+            init_code_hash
+        );
+        assert_eq!(
+            factory.sender(alice).compute_lottery_address(alice, salt),
+            expected
+        );
+        assert_ne!(
+            factory.sender(alice).compute_lottery_address(bob, salt),
+            expected
+        );
+    }
+
+    #[motsu::test]
+    fn create_lottery_falls_back_to_the_default_wrapper(
+        factory: Contract<LotteryFactory>,
+        alice: Address,
+    ) {
+        deploy(&factory, alice);
+        let wrapper = Address::repeat_byte(0x44);
+
+        assert_eq!(
+            factory.sender(alice).vrf_wrapper_or_default(Address::ZERO),
+            WRAPPER
+        );
+        assert_eq!(
+            factory.sender(alice).vrf_wrapper_or_default(wrapper),
+            wrapper
+        );
+    }
+
+    #[motsu::test]
+    fn register_lists_the_lottery_as_active(
+        factory: Contract<LotteryFactory>,
+        alice: Address,
+        bob: Address,
+    ) {
+        deploy(&factory, alice);
+        let lottery = Address::repeat_byte(1);
+
+        register(&factory, bob, lottery);
+
+        assert_eq!(factory.sender(bob).get_lottery_count(), U256::from(1));
+        assert_eq!(factory.sender(bob).get_lottery(U256::ZERO), lottery);
+        assert_eq!(factory.sender(bob).get_active_lotteries(), vec![lottery]);
+        let (creator, entry_fee, vrf_wrapper, _, active) =
+            factory.sender(bob).get_lottery_info(lottery);
+        assert_eq!(
+            (creator, entry_fee, vrf_wrapper, active),
+            (bob, ENTRY_FEE, WRAPPER, true)
+        );
+        assert!(factory.emitted(&LotteryCreated {
+            lottery,
+            creator: bob,
+            salt: B256::repeat_byte(1),
+            entryFee: ENTRY_FEE,
+            vrfWrapper: WRAPPER,
+        }));
+    }
+
+    #[motsu::test]
+    fn deactivate_lottery_swaps_the_last_lottery_in(
+        factory: Contract<LotteryFactory>,
+        alice: Address,
+        bob: Address,
+    ) {
+        deploy(&factory, alice);
+        let [first, second, third] = [1, 2, 3].map(Address::repeat_byte);
+        for lottery in [first, second, third] {
+            register(&factory, bob, lottery);
+        }
+
+        factory.sender(bob).deactivate_lottery(first).unwrap();
+        assert_eq!(
+            factory.sender(bob).get_active_lotteries(),
+            vec![third, second]
+        );
+
+        // The factory owner can deactivate too, and the moved lottery's index held
+        factory.sender(alice).deactivate_lottery(third).unwrap();
+        assert_eq!(factory.sender(bob).get_active_lotteries(), vec![second]);
+
+        factory.sender(bob).deactivate_lottery(second).unwrap();
+        assert!(factory.sender(bob).get_active_lotteries().is_empty());
+        assert_eq!(factory.sender(bob).get_lottery_count(), U256::from(3));
+        assert!(factory.sender(bob).is_registered(first));
+        assert!(!factory.sender(bob).get_lottery_info(first).4);
+    }
+
+    #[motsu::test]
+    fn deactivate_lottery_checks_the_lottery_and_the_caller(
+        factory: Contract<LotteryFactory>,
+        alice: Address,
+        bob: Address,
+        carol: Address,
+    ) {
+        deploy(&factory, alice);
+        let lottery = Address::repeat_byte(1);
+
+        let err = factory.sender(bob).deactivate_lottery(lottery).unwrap_err();
+        assert!(matches!(err, Error::LotteryNotFound(_)));
+
+        register(&factory, bob, lottery);
+        let err = factory
+            .sender(carol)
+            .deactivate_lottery(lottery)
+            .unwrap_err();
+        assert!(matches!(err, Error::NotLotteryCreator(_)));
+
+        factory.sender(bob).deactivate_lottery(lottery).unwrap();
+        let err = factory.sender(bob).deactivate_lottery(lottery).unwrap_err();
+        assert!(matches!(err, Error::LotteryNotActive(_)));
+    }
+}
@@ -0,0 +1,4263 @@
+//!
+//! Chainlink VRF Lottery Contract in Stylus Rust
+//!
+//! A decentralized lottery system that uses Chainlink VRF for verifiable random winner selection.
+//!
+
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+
+use stylus_sdk::{
+    abi::Bytes as AbiBytes,
+    alloy_primitives::{aliases::B32, keccak256, Address, Bytes, B256, U16, U256, U32, U8},
+    alloy_sol_types::{sol, SolValue},
+    function_selector,
+    keccak_const::Keccak256,
+    prelude::*,
+    stylus_core::calls::context::Call,
+    stylus_core::log,
+};
+
+#[allow(deprecated)]
+use stylus_sdk::call::Call as OldCall;
+
+use stylus_common::{
+    pause::PauseScope,
+    reentrancy_guard::{self, ReentrancyGuard},
+    timelock::{self, Timelock},
+};
+
+use openzeppelin_stylus::{
+    access::{
+        control::{self, AccessControl, IAccessControl},
+        ownable,
+        ownable_two_step::{IOwnable2Step, Ownable2Step},
+    },
+    proxy::utils::{
+        erc1822::IErc1822Proxiable,
+        uups_upgradeable::{self, IUUPSUpgradeable, UUPSUpgradeable},
+    },
+    token::{
+        erc1155::receiver::SINGLE_TRANSFER_FN_SELECTOR, erc721::receiver::RECEIVER_FN_SELECTOR,
+    },
+    utils::{
+        cryptography::eip712::IEip712,
+        nonces::{self, INonces, Nonces},
+        pausable::{self, IPausable, Pausable},
+        precompiles::{primitives::ecrecover, Precompiles},
+    },
+};
+
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Depth of the per-round participants Merkle tree (up to 2^32 tickets)
+const MERKLE_TREE_DEPTH: usize = 32;
+
+/// EIP-712 type string of a signed entry
+const ENTER_LOTTERY_TYPE: &[u8] =
+    b"EnterLottery(address player,uint256 roundId,uint256 tickets,uint256 deadline,uint256 nonce)";
+
+/// Game created by the constructor; the unkeyed entry points act on it
+const DEFAULT_LOTTERY_ID: u64 = 1;
+
+/// Sets VRF parameters, fees, payout rules and creates games
+pub const CONFIG_ADMIN_ROLE: [u8; 32] = Keccak256::new().update(b"CONFIG_ADMIN_ROLE").finalize();
+
+/// Starts draws and cancels, pauses or resumes rounds
+pub const OPERATOR_ROLE: [u8; 32] = Keccak256::new().update(b"OPERATOR_ROLE").finalize();
+
+/// Withdraws surplus and sets the treasury
+pub const TREASURER_ROLE: [u8; 32] = Keccak256::new().update(b"TREASURER_ROLE").finalize();
+
+/// Flips the entries, VRF request and payout pause switches
+pub const GUARDIAN_ROLE: [u8; 32] = Keccak256::new().update(b"GUARDIAN_ROLE").finalize();
+
+/// Roles that go with ownership: granted to the owner on deployment and moved to the
+/// new owner on `accept_ownership`
+const OWNER_ROLES: [[u8; 32]; 5] = [
+    AccessControl::DEFAULT_ADMIN_ROLE,
+    CONFIG_ADMIN_ROLE,
+    OPERATOR_ROLE,
+    TREASURER_ROLE,
+    GUARDIAN_ROLE,
+];
+
+/// Minimum delay between proposing a new VRF wrapper and switching to it
+const VRF_WRAPPER_MIGRATION_DELAY: u64 = 2 * 24 * 60 * 60;
+
+/// How long a replaced VRF wrapper can still fulfil the requests it issued
+const VRF_WRAPPER_GRACE_PERIOD: u64 = 24 * 60 * 60;
+
+/// Minimum delay between queueing a timelocked admin operation and executing it
+const DEFAULT_TIMELOCK_DELAY: u64 = 24 * 60 * 60;
+
+/// Most tickets a season pass buys per round; enrolment records each one
+const MAX_SUBSCRIPTION_TICKETS: u64 = 100;
+
+/// Re-rolls attempted per NFT prize when looking for a winner who hasn't won yet
+const MAX_PRIZE_DRAW_ATTEMPTS: u64 = 16;
+
+// Define persistent storage. Behind a proxy these slots are the proxy's state, so
+// fields are only ever appended; embedded components reserve gaps to grow in place.
+sol_storage! {
+    #[entrypoint]
+    pub struct Lottery {
+        address i_vrf_v2_plus_wrapper;
+        uint32 callback_gas_limit;
+        uint16 request_confirmations;
+        uint32 num_words;
+        
+        mapping(uint256 => address[]) players;
+        uint256 current_prize_pool;
+        mapping(uint256 => uint8) round_states;
+        
+        mapping(uint256 => uint256) vrf_requests;
+        
+        Ownable2Step ownable;
+
+        address treasury;
+        NftPrize[] nft_prizes;
+        uint256 next_unassigned_prize;
+
+        uint256 jackpot_rollover_bps;
+        uint256 min_players_for_payout;
+
+        mapping(uint256 => uint256) round_sponsorship;
+        uint256 guaranteed_min_prize;
+
+        uint256 future_sponsorship;
+        mapping(address => uint256) pending_winnings;
+        uint256 total_pending_winnings;
+        uint256 vrf_budget_reserve;
+
+        mapping(uint256 => uint256) round_entry_total;
+        mapping(uint256 => mapping(address => uint256)) round_contributions;
+        uint256 total_refunds_owed;
+
+        mapping(uint256 => uint256) round_random_word;
+        mapping(uint256 => address) round_winner;
+
+        mapping(uint256 => mapping(uint256 => bytes32)) merkle_branch;
+        mapping(uint256 => bytes32) round_participants_root;
+
+        mapping(uint256 => bytes32) round_allowlist_root;
+        mapping(uint256 => mapping(address => uint256)) round_allowlist_entries;
+
+        Nonces nonces;
+        mapping(address => uint256) prepaid_balance;
+        uint256 total_prepaid;
+
+        address[] subscribers;
+        mapping(address => bool) subscriber_listed;
+        mapping(address => Subscription) subscriptions;
+        uint256 total_subscription_balance;
+        mapping(uint256 => uint256) round_enrolment_cursor;
+
+        uint256 syndicate_count;
+        mapping(uint256 => Syndicate) syndicates;
+        mapping(uint256 => mapping(address => uint256)) syndicate_contributions;
+        mapping(uint256 => mapping(address => bool)) syndicate_claimed;
+        mapping(uint256 => mapping(uint256 => uint256)) ticket_syndicate;
+        uint256 total_syndicate_prizes;
+
+        uint256 referral_bps;
+        mapping(address => address) referrer_of;
+        mapping(address => uint256) referral_count;
+        mapping(address => uint256) referral_earnings;
+        mapping(address => uint256) referral_balances;
+        uint256 total_referral_balances;
+
+        mapping(address => PlayerStats) player_stats;
+        mapping(uint256 => mapping(address => uint256)) round_player_tickets;
+
+        uint256 lottery_count;
+        mapping(uint256 => Game) games;
+
+        Pausable entries_pausable;
+        Pausable vrf_requests_pausable;
+        Pausable payouts_pausable;
+
+        AccessControl access;
+
+        mapping(uint256 => address) vrf_request_wrapper;
+        mapping(address => uint256) vrf_wrapper_retired_until;
+        // ETA `propose_vrf_wrapper` queued each migration at
+        mapping(address => uint256) vrf_wrapper_proposal_eta;
+
+        Timelock timelock;
+
+        bool initialized;
+        UUPSUpgradeable uups;
+
+        ReentrancyGuard reentrancy;
+
+        mapping(uint256 => uint8) round_outcomes;
+    }
+
+    /// An independent game: its own fee, round sequence and pot. Rounds of every
+    /// game share the per-round mappings through `round_key`.
+    pub struct Game {
+        uint256 entry_fee;
+        uint256 current_round;
+        uint256 prize_pool;
+        uint256 jackpot_reserve;
+        address last_winner;
+        uint256 last_prize;
+        uint256 next_entry_fee;
+        bool has_next_entry_fee;
+    }
+
+    /// An escrowed ERC-721 or ERC-1155 token awarded to a single winner
+    pub struct NftPrize {
+        address token;
+        uint256 token_id;
+        uint256 amount;
+        bool is_erc1155;
+        address winner;
+        bool claimed;
+    }
+
+    /// Season pass prepaid at `price_per_round` for `rounds_remaining` rounds of game
+    /// `lottery_id`
+    pub struct Subscription {
+        uint256 rounds_remaining;
+        uint256 tickets_per_round;
+        uint256 price_per_round;
+        uint256 last_enrolled_round;
+        uint256 lottery_id;
+    }
+
+    /// Lifetime counters for a player's profile
+    pub struct PlayerStats {
+        uint256 rounds_played;
+        uint256 tickets_bought;
+        uint256 wins;
+        uint256 total_winnings;
+    }
+
+    /// Group of players buying tickets jointly for one round. Its tickets are held
+    /// by the contract and `prize` is split pro rata to `total_contributed`.
+    pub struct Syndicate {
+        address creator;
+        uint256 round_id;
+        uint256 total_contributed;
+        uint256 tickets;
+        uint256 prize;
+    }
+}
+
+/// Lifecycle of a lottery round, stored as `uint8` per round id
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RoundState {
+    /// Not opened yet (every future round id)
+    Pending = 0,
+    Open = 1,
+    Drawing = 2,
+    Settled = 3,
+    Cancelled = 4,
+    Paused = 5,
+    /// Randomness recorded, waiting for `settle_round`
+    ReadyToSettle = 6,
+}
+
+impl From<u8> for RoundState {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => RoundState::Open,
+            2 => RoundState::Drawing,
+            3 => RoundState::Settled,
+            4 => RoundState::Cancelled,
+            5 => RoundState::Paused,
+            6 => RoundState::ReadyToSettle,
+            _ => RoundState::Pending,
+        }
+    }
+}
+
+/// How a settled round paid out, stored as `uint8` per round id
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RoundOutcome {
+    /// Not settled
+    None = 0,
+    /// The pot went to the ticket `select_ticket` picked
+    Winner = 1,
+    /// Below `min_players_for_payout`: the whole pot rolled over
+    NoWinner = 2,
+    /// NFT prizes went to raffle winners and ticket revenue to the treasury
+    Raffle = 3,
+}
+
+impl From<u8> for RoundOutcome {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => RoundOutcome::Winner,
+            2 => RoundOutcome::NoWinner,
+            3 => RoundOutcome::Raffle,
+            _ => RoundOutcome::None,
+        }
+    }
+}
+
+// VRF V2+ Wrapper interface
+sol_interface! {
+    interface IVRFV2PlusWrapper {
+        function calculateRequestPriceNative(uint32 _callback_gas_limit, uint32 _num_words) external view returns (uint256);
+        function requestRandomWordsInNative(
+            uint32 _callback_gas_limit,
+            uint16 _request_confirmations,
+            uint32 _num_words,
+            bytes calldata extra_args
+        ) external payable returns (uint256 requestId);
+    }
+
+    interface IERC721 {
+        function safeTransferFrom(address from, address to, uint256 token_id) external;
+    }
+
+    interface IERC1155 {
+        function safeTransferFrom(address from, address to, uint256 id, uint256 value, bytes calldata data) external;
+    }
+}
+
+// Events
+sol! {
+    event LotteryEntered(address indexed player, uint256 indexed lotteryId, uint256 entryFee);
+    event DrawStarted(uint256 indexed lotteryId, uint256 indexed requestId, uint256 playersCount, bytes32 participantsRoot);
+    event WinnerSelected(uint256 indexed lotteryId, address indexed winner, uint256 prizeAmount, uint256 randomWord);
+    event EntryFeeUpdated(uint256 oldFee, uint256 newFee);
+    event LotteryCreated(uint256 indexed lotteryId, uint256 entryFee, uint256 firstRoundId);
+    event PauseUpdated(uint8 indexed scope, bool paused, address guardian);
+    event GameEntryFeeUpdated(uint256 indexed lotteryId, uint256 oldFee, uint256 newFee);
+    event GameEntryFeeScheduled(uint256 indexed lotteryId, uint256 newFee, uint256 effectiveRoundId);
+    event VRFRequestSent(uint256 indexed requestId, uint32 numWords);
+    event VRFRequestFulfilled(uint256 indexed requestId, uint256[] randomWords, uint256 payment);
+    event Received(address indexed sender, uint256 value);
+    event NftPrizeDeposited(uint256 indexed prizeId, address indexed token, uint256 tokenId, uint256 amount);
+    event NftPrizeAwarded(uint256 indexed lotteryId, uint256 indexed prizeId, address indexed winner);
+    event NftPrizeClaimed(uint256 indexed prizeId, address indexed winner);
+    event TreasuryPaid(uint256 indexed lotteryId, address indexed treasury, uint256 amount);
+    event TreasuryUpdated(address oldTreasury, address newTreasury);
+    event JackpotRolledOver(uint256 indexed lotteryId, uint256 amount, bool noWinner);
+    event JackpotRolloverUpdated(uint256 oldRolloverBps, uint256 newRolloverBps);
+    event MinPlayersForPayoutUpdated(uint256 oldMinPlayers, uint256 newMinPlayers);
+    event RoundSponsored(uint256 indexed round, address indexed sponsor, uint256 amount);
+    event GuaranteedMinPrizeUpdated(uint256 oldMinPrize, uint256 newMinPrize);
+    event WinningsPending(address indexed winner, uint256 amount);
+    event WinningsClaimed(address indexed winner, uint256 amount);
+    event VRFBudgetReserveUpdated(uint256 oldReserve, uint256 newReserve);
+    event Withdrawn(address indexed to, uint256 amount);
+    event VRFWrapperProposed(address indexed currentWrapper, address indexed newWrapper, uint256 eta);
+    event VRFWrapperProposalCancelled(address indexed newWrapper);
+    event VRFWrapperUpdated(address indexed oldWrapper, address indexed newWrapper, uint256 oldWrapperGraceEnd);
+    event RoundStateChanged(uint256 indexed lotteryId, uint256 indexed roundId, uint8 previousState, uint8 newState);
+    event RefundClaimed(uint256 indexed roundId, address indexed player, uint256 amount);
+    event AllowlistRootUpdated(uint256 indexed roundId, bytes32 root);
+    event PrepaidDeposited(address indexed player, address indexed from, uint256 amount);
+    event PrepaidWithdrawn(address indexed player, uint256 amount);
+    event Subscribed(address indexed player, uint256 indexed lotteryId, uint256 rounds, uint256 ticketsPerRound, uint256 pricePerRound);
+    event Unsubscribed(address indexed player, uint256 roundsRefunded, uint256 refund);
+    event SubscribersEnrolled(uint256 indexed roundId, uint256 enrolled, uint256 cursor);
+    event ReferrerRegistered(address indexed player, address indexed referrer);
+    event ReferralRewarded(address indexed referrer, address indexed player, uint256 indexed roundId, uint256 amount);
+    event ReferralRewardsClaimed(address indexed referrer, uint256 amount);
+    event ReferralBpsUpdated(uint256 oldReferralBps, uint256 newReferralBps);
+    event SyndicateCreated(uint256 indexed syndicateId, uint256 indexed roundId, address indexed creator);
+    event SyndicateContribution(uint256 indexed syndicateId, address indexed member, uint256 amount, uint256 tickets);
+    event SyndicateWon(uint256 indexed syndicateId, uint256 indexed roundId, uint256 prize);
+    event SyndicateShareClaimed(uint256 indexed syndicateId, address indexed member, uint256 amount);
+    event SignedEntry(address indexed player, address indexed relayer, uint256 indexed roundId, uint256 tickets, uint256 nonce);
+}
+
+// Errors
+sol! {
+    #[derive(Debug)]
+    error OnlyVRFWrapperCanFulfill(address have, address want);
+
+    #[derive(Debug)]
+    error InvalidVRFWrapper(address wrapper);
+
+    #[derive(Debug)]
+    error VRFWrapperNotProposed(address wrapper);
+    
+    #[derive(Debug)]
+    error InsufficientEntryFee(uint256 sent, uint256 required);
+    
+    #[derive(Debug)]
+    error NoPlayersInLottery();
+    
+    #[derive(Debug)]
+    error TransferFailed();
+
+    #[derive(Debug)]
+    error UnauthorizedPrizeDeposit(address from);
+
+    #[derive(Debug)]
+    error NftPrizeNotFound(uint256 prizeId);
+
+    #[derive(Debug)]
+    error NotPrizeWinner(uint256 prizeId, address caller);
+
+    #[derive(Debug)]
+    error PrizeAlreadyClaimed(uint256 prizeId);
+
+    #[derive(Debug)]
+    error InvalidRolloverBps(uint256 rolloverBps);
+
+    #[derive(Debug)]
+    error InvalidSponsorRound(uint256 round);
+
+    #[derive(Debug)]
+    error GuaranteedPrizeNotMet(uint256 prizePool, uint256 required);
+
+    #[derive(Debug)]
+    error NoPendingWinnings(address winner);
+
+    #[derive(Debug)]
+    error InsufficientSurplus(uint256 requested, uint256 available);
+
+    #[derive(Debug)]
+    error InsufficientVrfBudget(uint256 price, uint256 budget);
+
+    #[derive(Debug)]
+    error InvalidRoundState(uint256 roundId, uint8 state);
+
+    #[derive(Debug)]
+    error InvalidPauseScope(uint8 scope);
+
+    #[derive(Debug)]
+    error NoRefundOwed(uint256 roundId, address player);
+
+    #[derive(Debug)]
+    error AllowlistRequired(uint256 roundId);
+
+    #[derive(Debug)]
+    error InvalidAllowlistProof(address player);
+
+    #[derive(Debug)]
+    error TicketAllowanceExceeded(address player, uint256 maxTickets);
+
+    #[derive(Debug)]
+    error SignatureExpired(uint256 deadline);
+
+    #[derive(Debug)]
+    error InvalidSignatureLength(uint256 length);
+
+    #[derive(Debug)]
+    error InvalidSigner(address signer, address player);
+
+    #[derive(Debug)]
+    error InvalidTicketCount(uint256 tickets);
+
+    #[derive(Debug)]
+    error InsufficientPrepaidBalance(uint256 balance, uint256 required);
+
+    #[derive(Debug)]
+    error InvalidSubscriptionPayment(uint256 sent, uint256 required);
+
+    #[derive(Debug)]
+    error SubscriptionPriceOverflow(uint256 rounds, uint256 ticketsPerRound);
+
+    #[derive(Debug)]
+    error InsufficientSubscriptionBalance(uint256 balance, uint256 required);
+
+    #[derive(Debug)]
+    error AlreadySubscribed(address player);
+
+    #[derive(Debug)]
+    error NotSubscribed(address player);
+
+    #[derive(Debug)]
+    error SyndicateNotFound(uint256 syndicateId);
+
+    #[derive(Debug)]
+    error SyndicateClosed(uint256 syndicateId);
+
+    #[derive(Debug)]
+    error InvalidSyndicateContribution(uint256 sent, uint256 entryFee);
+
+    #[derive(Debug)]
+    error NoSyndicateShare(uint256 syndicateId, address member);
+
+    #[derive(Debug)]
+    error InvalidReferrer(address referrer);
+
+    #[derive(Debug)]
+    error ReferrerAlreadySet(address player, address referrer);
+
+    #[derive(Debug)]
+    error InvalidReferralBps(uint256 referralBps);
+
+    #[derive(Debug)]
+    error NoReferralRewards(address referrer);
+
+    #[derive(Debug)]
+    error LotteryNotFound(uint256 lotteryId);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Error {
+    OnlyVRFWrapperCanFulfill(OnlyVRFWrapperCanFulfill),
+    InvalidVRFWrapper(InvalidVRFWrapper),
+    VRFWrapperNotProposed(VRFWrapperNotProposed),
+    InsufficientEntryFee(InsufficientEntryFee),
+    NoPlayersInLottery(NoPlayersInLottery),
+    TransferFailed(TransferFailed),
+    UnauthorizedPrizeDeposit(UnauthorizedPrizeDeposit),
+    NftPrizeNotFound(NftPrizeNotFound),
+    NotPrizeWinner(NotPrizeWinner),
+    PrizeAlreadyClaimed(PrizeAlreadyClaimed),
+    InvalidRolloverBps(InvalidRolloverBps),
+    InvalidSponsorRound(InvalidSponsorRound),
+    GuaranteedPrizeNotMet(GuaranteedPrizeNotMet),
+    NoPendingWinnings(NoPendingWinnings),
+    InsufficientSurplus(InsufficientSurplus),
+    InsufficientVrfBudget(InsufficientVrfBudget),
+    InvalidRoundState(InvalidRoundState),
+    InvalidPauseScope(InvalidPauseScope),
+    NoRefundOwed(NoRefundOwed),
+    AllowlistRequired(AllowlistRequired),
+    InvalidAllowlistProof(InvalidAllowlistProof),
+    TicketAllowanceExceeded(TicketAllowanceExceeded),
+    SignatureExpired(SignatureExpired),
+    InvalidSignatureLength(InvalidSignatureLength),
+    InvalidSigner(InvalidSigner),
+    InvalidTicketCount(InvalidTicketCount),
+    InsufficientPrepaidBalance(InsufficientPrepaidBalance),
+    InvalidSubscriptionPayment(InvalidSubscriptionPayment),
+    SubscriptionPriceOverflow(SubscriptionPriceOverflow),
+    InsufficientSubscriptionBalance(InsufficientSubscriptionBalance),
+    AlreadySubscribed(AlreadySubscribed),
+    NotSubscribed(NotSubscribed),
+    SyndicateNotFound(SyndicateNotFound),
+    SyndicateClosed(SyndicateClosed),
+    InvalidSyndicateContribution(InvalidSyndicateContribution),
+    NoSyndicateShare(NoSyndicateShare),
+    InvalidReferrer(InvalidReferrer),
+    ReferrerAlreadySet(ReferrerAlreadySet),
+    InvalidReferralBps(InvalidReferralBps),
+    NoReferralRewards(NoReferralRewards),
+    LotteryNotFound(LotteryNotFound),
+    EnforcedPause(pausable::EnforcedPause),
+    ExpectedPause(pausable::ExpectedPause),
+    InvalidAccountNonce(nonces::InvalidAccountNonce),
+    InvalidSignature(ecrecover::ECDSAInvalidSignature),
+    InvalidSignatureS(ecrecover::ECDSAInvalidSignatureS),
+    UnauthorizedAccount(ownable::OwnableUnauthorizedAccount),
+    InvalidOwner(ownable::OwnableInvalidOwner),
+    AccessControlUnauthorizedAccount(control::AccessControlUnauthorizedAccount),
+    AccessControlBadConfirmation(control::AccessControlBadConfirmation),
+    TimelockInsufficientDelay(timelock::TimelockInsufficientDelay),
+    TimelockAlreadyQueued(timelock::TimelockAlreadyQueued),
+    TimelockNotQueued(timelock::TimelockNotQueued),
+    TimelockNotReady(timelock::TimelockNotReady),
+    TimelockExpired(timelock::TimelockExpired),
+    InvalidInitialization(uups_upgradeable::InvalidInitialization),
+    ReentrantCall(reentrancy_guard::ReentrancyGuardReentrantCall),
+}
+
+impl From<ownable::Error> for Error {
+    fn from(value: ownable::Error) -> Self {
+        match value {
+            ownable::Error::UnauthorizedAccount(e) => Error::UnauthorizedAccount(e),
+            ownable::Error::InvalidOwner(e) => Error::InvalidOwner(e),
+        }
+    }
+}
+
+impl From<control::Error> for Error {
+    fn from(value: control::Error) -> Self {
+        match value {
+            control::Error::UnauthorizedAccount(e) => Error::AccessControlUnauthorizedAccount(e),
+            control::Error::BadConfirmation(e) => Error::AccessControlBadConfirmation(e),
+        }
+    }
+}
+
+impl From<timelock::Error> for Error {
+    fn from(value: timelock::Error) -> Self {
+        match value {
+            timelock::Error::InsufficientDelay(e) => Error::TimelockInsufficientDelay(e),
+            timelock::Error::AlreadyQueued(e) => Error::TimelockAlreadyQueued(e),
+            timelock::Error::NotQueued(e) => Error::TimelockNotQueued(e),
+            timelock::Error::NotReady(e) => Error::TimelockNotReady(e),
+            timelock::Error::Expired(e) => Error::TimelockExpired(e),
+        }
+    }
+}
+
+impl From<reentrancy_guard::Error> for Error {
+    fn from(value: reentrancy_guard::Error) -> Self {
+        match value {
+            reentrancy_guard::Error::ReentrantCall(e) => Error::ReentrantCall(e),
+        }
+    }
+}
+
+impl From<pausable::Error> for Error {
+    fn from(value: pausable::Error) -> Self {
+        match value {
+            pausable::Error::EnforcedPause(e) => Error::EnforcedPause(e),
+            pausable::Error::ExpectedPause(e) => Error::ExpectedPause(e),
+        }
+    }
+}
+
+impl From<nonces::Error> for Error {
+    fn from(value: nonces::Error) -> Self {
+        match value {
+            nonces::Error::InvalidAccountNonce(e) => Error::InvalidAccountNonce(e),
+        }
+    }
+}
+
+impl From<ecrecover::Error> for Error {
+    fn from(value: ecrecover::Error) -> Self {
+        match value {
+            ecrecover::Error::InvalidSignature(e) => Error::InvalidSignature(e),
+            ecrecover::Error::InvalidSignatureS(e) => Error::InvalidSignatureS(e),
+        }
+    }
+}
+
+impl IEip712 for Lottery {
+    const NAME: &'static str = "Lottery";
+    const VERSION: &'static str = "1";
+}
+
+#[public]
+impl Lottery {
+    /// Constructor - initializes the lottery contract. Also marks the deployment as an
+    /// implementation, so a directly deployed lottery can't be upgraded.
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        vrf_v2_plus_wrapper: Address,
+        entry_fee: U256,
+        owner: Address,
+    ) -> Result<(), Error> {
+        self.uups.constructor();
+        self.init(vrf_v2_plus_wrapper, entry_fee, owner)
+    }
+
+    /// Initializer replacing the constructor for proxied deployments; the proxy
+    /// delegate-calls it on deployment. Only callable once, and only through a proxy.
+    pub fn initialize(
+        &mut self,
+        vrf_v2_plus_wrapper: Address,
+        entry_fee: U256,
+        owner: Address,
+    ) -> Result<(), Vec<u8>> {
+        self.uups.set_version()?;
+        Ok(self.init(vrf_v2_plus_wrapper, entry_fee, owner)?)
+    }
+
+    /// Owner moves the proxy to `new_implementation`. The new code must keep this
+    /// storage layout, only appending fields. Timelocked like `upgrade_to_and_call`
+    /// with empty `data`.
+    pub fn upgrade_to(&mut self, new_implementation: Address) -> Result<(), Vec<u8>> {
+        self.upgrade_to_and_call(new_implementation, AbiBytes::from(Vec::new()))
+    }
+
+    /// Owner upgrades and delegate-calls `data` on the new implementation in the
+    /// same transaction, e.g. to migrate state.
+    /// Timelocked: queue `upgradeToAndCall(address,bytes)` first.
+    /// Always reverts on the factory's EIP-1167 clones, which have no ERC-1967
+    /// implementation slot.
+    pub fn upgrade_to_and_call(
+        &mut self,
+        new_implementation: Address,
+        data: AbiBytes,
+    ) -> Result<(), Vec<u8>> {
+        self.ownable.ownable.only_owner()?;
+        self.timelocked(
+            function_selector!("upgradeToAndCall", Address, AbiBytes),
+            (new_implementation, Bytes::copy_from_slice(&data)).abi_encode_params(),
+        )?;
+        self.uups.upgrade_to_and_call(new_implementation, data)
+    }
+
+    /// Records this implementation's version in the proxy; called by `upgrade_to_and_call`
+    pub fn set_version(&mut self) -> Result<(), Vec<u8>> {
+        Ok(self.uups.set_version()?)
+    }
+
+    #[selector(name = "proxiableUUID")]
+    pub fn proxiable_uuid(&self) -> Result<B256, Vec<u8>> {
+        self.uups.proxiable_uuid()
+    }
+
+    #[selector(name = "UPGRADE_INTERFACE_VERSION")]
+    pub fn upgrade_interface_version(&self) -> String {
+        self.uups.upgrade_interface_version()
+    }
+
+    /// Config admin opens another independent game; returns its lottery id
+    pub fn create_lottery(&mut self, entry_fee: U256) -> Result<U256, Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        Ok(self.open_lottery(entry_fee))
+    }
+
+    /// Players can enter the lottery, unless the round is allowlist-gated
+    #[payable]
+    pub fn enter_lottery(&mut self) -> Result<(), Error> {
+        self.enter_game(U256::from(DEFAULT_LOTTERY_ID))
+    }
+
+    /// Players enter the open round of any game
+    #[payable]
+    pub fn enter_game(&mut self, lottery_id: U256) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            let round = this.require_current_state(lottery_id, &[RoundState::Open])?;
+
+            if !this.round_allowlist_root.get(round).is_zero() {
+                return Err(Error::AllowlistRequired(AllowlistRequired {
+                    roundId: round,
+                }));
+            }
+
+            let player = this.vm().msg_sender();
+            let msg_value = this.vm().msg_value();
+            this.enter(round, player, U256::from(1), msg_value)?;
+
+            Ok(())
+        })
+    }
+
+    /// Enters the default game with a referrer
+    #[payable]
+    pub fn enter_lottery_with_referrer(&mut self, referrer: Address) -> Result<(), Error> {
+        self.enter_game_with_referrer(U256::from(DEFAULT_LOTTERY_ID), referrer)
+    }
+
+    /// Enters a game with a referrer, who is credited `referral_bps` of the entry fee.
+    /// A player's first referrer sticks; the share is taken out of the pot, so
+    /// a cancelled round only refunds what went into it.
+    #[payable]
+    pub fn enter_game_with_referrer(
+        &mut self,
+        lottery_id: U256,
+        referrer: Address,
+    ) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.entries_pausable.when_not_paused()?;
+            let round = this.require_current_state(lottery_id, &[RoundState::Open])?;
+
+            if !this.round_allowlist_root.get(round).is_zero() {
+                return Err(Error::AllowlistRequired(AllowlistRequired {
+                    roundId: round,
+                }));
+            }
+
+            let player = this.vm().msg_sender();
+            if referrer.is_zero() || referrer == player {
+                return Err(Error::InvalidReferrer(InvalidReferrer { referrer }));
+            }
+
+            let current_referrer = this.referrer_of.get(player);
+            if current_referrer.is_zero() {
+                this.referrer_of.insert(player, referrer);
+                let count = this.referral_count.get(referrer);
+                this.referral_count.insert(referrer, count + U256::from(1));
+
+                log(this.vm(), ReferrerRegistered { player, referrer });
+            } else if current_referrer != referrer {
+                return Err(Error::ReferrerAlreadySet(ReferrerAlreadySet {
+                    player,
+                    referrer: current_referrer,
+                }));
+            }
+
+            let msg_value = this.vm().msg_value();
+            let required_fee = this.games.get(lottery_id).entry_fee.get();
+            if msg_value < required_fee {
+                return Err(Error::InsufficientEntryFee(InsufficientEntryFee {
+                    sent: msg_value,
+                    required: required_fee,
+                }));
+            }
+
+            let reward = required_fee * this.referral_bps.get() / U256::from(BPS_DENOMINATOR);
+            if !reward.is_zero() {
+                let balance = this.referral_balances.get(referrer);
+                this.referral_balances.insert(referrer, balance + reward);
+                let earnings = this.referral_earnings.get(referrer);
+                this.referral_earnings.insert(referrer, earnings + reward);
+                let total = this.total_referral_balances.get();
+                this.total_referral_balances.set(total + reward);
+
+                log(
+                    this.vm(),
+                    ReferralRewarded {
+                        referrer,
+                        player,
+                        roundId: round,
+                        amount: reward,
+                    },
+                );
+            }
+
+            this.add_tickets(round, player, U256::from(1), msg_value - reward);
+
+            Ok(())
+        })
+    }
+
+    /// Referrers pull their accumulated rewards
+    pub fn claim_referral_rewards(&mut self) -> Result<U256, Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            let referrer = this.vm().msg_sender();
+            let amount = this.referral_balances.get(referrer);
+            if amount.is_zero() {
+                return Err(Error::NoReferralRewards(NoReferralRewards { referrer }));
+            }
+
+            this.referral_balances.insert(referrer, U256::ZERO);
+            let total = this.total_referral_balances.get();
+            this.total_referral_balances.set(total - amount);
+
+            this.vm()
+                .call(&Call::new().value(amount), referrer, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(this.vm(), ReferralRewardsClaimed { referrer, amount });
+
+            Ok(amount)
+        })
+    }
+
+    /// Config admin sets the share of the entry fee credited to referrers
+    pub fn set_referral_bps(&mut self, referral_bps: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setReferralBps", U256),
+            (referral_bps,).abi_encode_params(),
+        )?;
+        if referral_bps > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::InvalidReferralBps(InvalidReferralBps {
+                referralBps: referral_bps,
+            }));
+        }
+
+        let old_bps = self.referral_bps.get();
+        self.referral_bps.set(referral_bps);
+
+        log(
+            self.vm(),
+            ReferralBpsUpdated {
+                oldReferralBps: old_bps,
+                newReferralBps: referral_bps,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Allowlisted players enter the default game's gated round
+    #[payable]
+    pub fn enter_lottery_with_proof(
+        &mut self,
+        proof: Vec<B256>,
+        max_tickets: U256,
+    ) -> Result<(), Error> {
+        self.enter_game_with_proof(U256::from(DEFAULT_LOTTERY_ID), proof, max_tickets)
+    }
+
+    /// Allowlisted players enter a game's gated round, up to `max_tickets` tickets per round.
+    /// Leaves are `keccak256(keccak256(abi.encode(address player, uint256 maxTickets)))`
+    /// with sorted-pair hashing, as built by OpenZeppelin's `StandardMerkleTree`.
+    /// Rounds without an allowlist root accept the call like `enter_game`.
+    #[payable]
+    pub fn enter_game_with_proof(
+        &mut self,
+        lottery_id: U256,
+        proof: Vec<B256>,
+        max_tickets: U256,
+    ) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            let round = this.require_current_state(lottery_id, &[RoundState::Open])?;
+            let player = this.vm().msg_sender();
+
+            let root = this.round_allowlist_root.get(round);
+            if !root.is_zero() {
+                if !verify_merkle_proof(&proof, root, allowlist_leaf(player, max_tickets)) {
+                    return Err(Error::InvalidAllowlistProof(InvalidAllowlistProof {
+                        player,
+                    }));
+                }
+
+                let entered = this.round_allowlist_entries.getter(round).get(player);
+                if entered >= max_tickets {
+                    return Err(Error::TicketAllowanceExceeded(TicketAllowanceExceeded {
+                        player,
+                        maxTickets: max_tickets,
+                    }));
+                }
+                this.round_allowlist_entries
+                    .setter(round)
+                    .insert(player, entered + U256::from(1));
+            }
+
+            let msg_value = this.vm().msg_value();
+            this.enter(round, player, U256::from(1), msg_value)?;
+
+            Ok(())
+        })
+    }
+
+    /// Relayers submit entries signed off-chain by the player (EIP-712, domain
+    /// "Lottery" version "1"). Tickets are paid from the player's prepaid balance.
+    /// `sig` is the 65-byte `r || s || v` signature over
+    /// `EnterLottery(address player,uint256 roundId,uint256 tickets,uint256 deadline,uint256 nonce)`.
+    pub fn enter_with_signature(
+        &mut self,
+        player: Address,
+        round_id: U256,
+        tickets: U256,
+        deadline: U256,
+        nonce: U256,
+        sig: AbiBytes,
+    ) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.require_round_state(round_id, &[RoundState::Open])?;
+            let lottery_id = lottery_of(round_id);
+            if round_id != this.games.get(lottery_id).current_round.get() {
+                return Err(Error::InvalidRoundState(InvalidRoundState {
+                    roundId: round_id,
+                    state: this.round_state(round_id) as u8,
+                }));
+            }
+            if !this.round_allowlist_root.get(round_id).is_zero() {
+                return Err(Error::AllowlistRequired(AllowlistRequired {
+                    roundId: round_id,
+                }));
+            }
+            if U256::from(this.vm().block_timestamp()) > deadline {
+                return Err(Error::SignatureExpired(SignatureExpired { deadline }));
+            }
+            if sig.len() != 65 {
+                return Err(Error::InvalidSignatureLength(InvalidSignatureLength {
+                    length: U256::from(sig.len()),
+                }));
+            }
+
+            this.nonces.use_checked_nonce(player, nonce)?;
+
+            let struct_hash = keccak256(
+                [
+                    keccak256(ENTER_LOTTERY_TYPE).as_slice(),
+                    B256::left_padding_from(player.as_slice()).as_slice(),
+                    &round_id.to_be_bytes::<32>(),
+                    &tickets.to_be_bytes::<32>(),
+                    &deadline.to_be_bytes::<32>(),
+                    &nonce.to_be_bytes::<32>(),
+                ]
+                .concat(),
+            );
+            let hash = this.hash_typed_data_v4(struct_hash);
+            let signer = this.ec_recover(
+                hash,
+                sig[64],
+                B256::from_slice(&sig[..32]),
+                B256::from_slice(&sig[32..64]),
+            )?;
+            if signer != player {
+                return Err(Error::InvalidSigner(InvalidSigner { signer, player }));
+            }
+
+            let cost = this.games.get(lottery_id).entry_fee.get() * tickets;
+            let balance = this.prepaid_balance.get(player);
+            if balance < cost {
+                return Err(Error::InsufficientPrepaidBalance(
+                    InsufficientPrepaidBalance {
+                    balance,
+                    required: cost,
+                    },
+                ));
+            }
+            this.prepaid_balance.insert(player, balance - cost);
+            let total_prepaid = this.total_prepaid.get();
+            this.total_prepaid.set(total_prepaid - cost);
+
+            this.enter(round_id, player, tickets, cost)?;
+
+            log(
+                this.vm(),
+                SignedEntry {
+                    player,
+                    relayer: this.vm().msg_sender(),
+                    roundId: round_id,
+                    tickets,
+                    nonce,
+                },
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Anyone can top up a player's prepaid balance for signed entries
+    #[payable]
+    pub fn deposit_prepaid(&mut self, player: Address) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            let amount = this.vm().msg_value();
+            let balance = this.prepaid_balance.get(player);
+            this.prepaid_balance.insert(player, balance + amount);
+            let total_prepaid = this.total_prepaid.get();
+            this.total_prepaid.set(total_prepaid + amount);
+
+            log(
+                this.vm(),
+                PrepaidDeposited {
+                    player,
+                    from: this.vm().msg_sender(),
+                    amount,
+                },
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Players withdraw unspent prepaid balance
+    pub fn withdraw_prepaid(&mut self, amount: U256) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            let player = this.vm().msg_sender();
+            let balance = this.prepaid_balance.get(player);
+            if balance < amount {
+                return Err(Error::InsufficientPrepaidBalance(
+                    InsufficientPrepaidBalance {
+                    balance,
+                    required: amount,
+                    },
+                ));
+            }
+
+            this.prepaid_balance.insert(player, balance - amount);
+            let total_prepaid = this.total_prepaid.get();
+            this.total_prepaid.set(total_prepaid - amount);
+
+            this.vm()
+                .call(&Call::new().value(amount), player, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(this.vm(), PrepaidWithdrawn { player, amount });
+
+            Ok(())
+        })
+    }
+
+    /// Prepays a season pass for the default game
+    #[payable]
+    pub fn subscribe(&mut self, rounds: U256, tickets_per_round: U256) -> Result<(), Error> {
+        self.subscribe_to_game(U256::from(DEFAULT_LOTTERY_ID), rounds, tickets_per_round)
+    }
+
+    /// Prepays `rounds` rounds of a game's `tickets_per_round` tickets at its current fee.
+    /// The caller is enrolled straight away when the current round is open; later
+    /// rounds are filled through `enroll_game_subscribers`.
+    #[payable]
+    pub fn subscribe_to_game(
+        &mut self,
+        lottery_id: U256,
+        rounds: U256,
+        tickets_per_round: U256,
+    ) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.entries_pausable.when_not_paused()?;
+            if tickets_per_round.is_zero()
+                || tickets_per_round > U256::from(MAX_SUBSCRIPTION_TICKETS)
+            {
+                return Err(Error::InvalidTicketCount(InvalidTicketCount {
+                    tickets: tickets_per_round,
+                }));
+            }
+
+            let player = this.vm().msg_sender();
+            if !this
+                .subscriptions
+                .get(player)
+                .rounds_remaining
+                .get()
+                .is_zero()
+            {
+                return Err(Error::AlreadySubscribed(AlreadySubscribed { player }));
+            }
+
+            let game_round = this.current_round_of(lottery_id)?;
+            let overflow = || {
+                Error::SubscriptionPriceOverflow(SubscriptionPriceOverflow {
+                    rounds,
+                    ticketsPerRound: tickets_per_round,
+                })
+            };
+            let price_per_round = this
+                .games
+                .get(lottery_id)
+                .entry_fee
+                .get()
+                .checked_mul(tickets_per_round)
+                .ok_or_else(overflow)?;
+            let required = price_per_round.checked_mul(rounds).ok_or_else(overflow)?;
+            let msg_value = this.vm().msg_value();
+            if rounds.is_zero() || msg_value != required {
+                return Err(Error::InvalidSubscriptionPayment(
+                    InvalidSubscriptionPayment {
+                        sent: msg_value,
+                        required,
+                    },
+                ));
+            }
+
+            if !this.subscriber_listed.get(player) {
+                this.subscriber_listed.insert(player, true);
+                this.subscribers.push(player);
+            }
+
+            let mut subscription = this.subscriptions.setter(player);
+            subscription.rounds_remaining.set(rounds);
+            subscription.tickets_per_round.set(tickets_per_round);
+            subscription.price_per_round.set(price_per_round);
+            subscription.lottery_id.set(lottery_id);
+
+            let total = this.total_subscription_balance.get();
+            this.total_subscription_balance
+                .set(total.checked_add(required).ok_or_else(overflow)?);
+
+            log(
+                this.vm(),
+                Subscribed {
+                    player,
+                    lotteryId: lottery_id,
+                    rounds,
+                    ticketsPerRound: tickets_per_round,
+                    pricePerRound: price_per_round,
+                },
+            );
+
+            if this.round_state(game_round) == RoundState::Open
+                && this.round_allowlist_root.get(game_round).is_zero()
+            {
+                this.enroll_subscriber(game_round, player)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Cancels the caller's season pass and refunds the rounds not yet enrolled
+    pub fn unsubscribe(&mut self) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            let player = this.vm().msg_sender();
+            let subscription = this.subscriptions.get(player);
+            let rounds = subscription.rounds_remaining.get();
+            if rounds.is_zero() {
+                return Err(Error::NotSubscribed(NotSubscribed { player }));
+            }
+
+            let refund = subscription.price_per_round.get() * rounds;
+            this.subscriptions
+                .setter(player)
+                .rounds_remaining
+                .set(U256::ZERO);
+            this.debit_subscription_balance(refund)?;
+
+            this.vm()
+                .call(&Call::new().value(refund), player, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(
+                this.vm(),
+                Unsubscribed {
+                    player,
+                    roundsRefunded: rounds,
+                    refund,
+                },
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Anyone can enrol up to `max_count` subscribers into the default game's open round
+    pub fn enroll_subscribers(&mut self, max_count: U256) -> Result<U256, Error> {
+        self.enroll_game_subscribers(U256::from(DEFAULT_LOTTERY_ID), max_count)
+    }
+
+    /// Anyone can enrol up to `max_count` of the subscribers list into a game's open
+    /// round, resuming from where the previous batch for that round stopped. Only
+    /// subscribers of that game are enrolled.
+    pub fn enroll_game_subscribers(
+        &mut self,
+        lottery_id: U256,
+        max_count: U256,
+    ) -> Result<U256, Error> {
+        self.entries_pausable.when_not_paused()?;
+        let round = self.require_current_state(lottery_id, &[RoundState::Open])?;
+        if !self.round_allowlist_root.get(round).is_zero() {
+            return Err(Error::AllowlistRequired(AllowlistRequired {
+                roundId: round,
+            }));
+        }
+
+        let total = U256::from(self.subscribers.len());
+        let mut cursor = self.round_enrolment_cursor.get(round);
+        let end = total.min(cursor.saturating_add(max_count));
+        let mut enrolled = U256::ZERO;
+        while cursor < end {
+            let player = self.subscribers.get(cursor).unwrap_or_default();
+            if self.enroll_subscriber(round, player)? {
+                enrolled += U256::from(1);
+            }
+            cursor += U256::from(1);
+        }
+        self.round_enrolment_cursor.insert(round, cursor);
+
+        log(
+            self.vm(),
+            SubscribersEnrolled {
+                roundId: round,
+                enrolled,
+                cursor,
+            },
+        );
+
+        Ok(enrolled)
+    }
+
+    /// Opens a syndicate for a game's current round; returns its id
+    pub fn create_syndicate(&mut self, lottery_id: U256) -> Result<U256, Error> {
+        self.entries_pausable.when_not_paused()?;
+        let round = self.require_current_state(lottery_id, &[RoundState::Open])?;
+        if !self.round_allowlist_root.get(round).is_zero() {
+            return Err(Error::AllowlistRequired(AllowlistRequired {
+                roundId: round,
+            }));
+        }
+
+        let syndicate_id = self.syndicate_count.get() + U256::from(1);
+        self.syndicate_count.set(syndicate_id);
+
+        let creator = self.vm().msg_sender();
+        let mut syndicate = self.syndicates.setter(syndicate_id);
+        syndicate.creator.set(creator);
+        syndicate.round_id.set(round);
+
+        log(
+            self.vm(),
+            SyndicateCreated {
+                syndicateId: syndicate_id,
+                roundId: round,
+                creator,
+            },
+        );
+
+        Ok(syndicate_id)
+    }
+
+    /// Buys tickets for a syndicate; the contribution must be a whole number of tickets
+    #[payable]
+    pub fn contribute_to_syndicate(&mut self, syndicate_id: U256) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.entries_pausable.when_not_paused()?;
+            let syndicate = this.syndicates.get(syndicate_id);
+            if syndicate.creator.get().is_zero() {
+                return Err(Error::SyndicateNotFound(SyndicateNotFound {
+                    syndicateId: syndicate_id,
+                }));
+            }
+            let round = syndicate.round_id.get();
+            let total_contributed = syndicate.total_contributed.get();
+            let total_tickets = syndicate.tickets.get();
+            let game = this.games.get(lottery_of(round));
+            let entry_fee = game.entry_fee.get();
+            if round != game.current_round.get() || this.round_state(round) != RoundState::Open {
+                return Err(Error::SyndicateClosed(SyndicateClosed {
+                    syndicateId: syndicate_id,
+                }));
+            }
+
+            let amount = this.vm().msg_value();
+            if amount.is_zero() || entry_fee.is_zero() || amount % entry_fee != U256::ZERO {
+                return Err(Error::InvalidSyndicateContribution(
+                    InvalidSyndicateContribution {
+                        sent: amount,
+                        entryFee: entry_fee,
+                    },
+                ));
+            }
+            let tickets = amount / entry_fee;
+
+            let holder = this.vm().contract_address();
+            let mut ticket = U256::ZERO;
+            while ticket < tickets {
+                let ticket_index = U256::from(this.round_players_count(round));
+                this.record_ticket(round, holder);
+                this.ticket_syndicate
+                    .setter(round)
+                    .insert(ticket_index, syndicate_id);
+                ticket += U256::from(1);
+            }
+
+            let member = this.vm().msg_sender();
+            this.credit_entry(round, member, amount);
+            this.record_player_tickets(round, member, tickets);
+
+            let contribution = this
+                .syndicate_contributions
+                .getter(syndicate_id)
+                .get(member);
+            this.syndicate_contributions
+                .setter(syndicate_id)
+                .insert(member, contribution + amount);
+            let mut syndicate = this.syndicates.setter(syndicate_id);
+            syndicate.total_contributed.set(total_contributed + amount);
+            syndicate.tickets.set(total_tickets + tickets);
+
+            log(
+                this.vm(),
+                SyndicateContribution {
+                    syndicateId: syndicate_id,
+                    member,
+                    amount,
+                    tickets,
+                },
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Members pull their pro-rata share of a syndicate's prize
+    pub fn claim_syndicate_share(&mut self, syndicate_id: U256) -> Result<U256, Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            let member = this.vm().msg_sender();
+            let amount = this.syndicate_share(syndicate_id, member);
+            if amount.is_zero() {
+                return Err(Error::NoSyndicateShare(NoSyndicateShare {
+                    syndicateId: syndicate_id,
+                    member,
+                }));
+            }
+
+            this.syndicate_claimed
+                .setter(syndicate_id)
+                .insert(member, true);
+            this.record_player_win(member, amount);
+            let total = this.total_syndicate_prizes.get();
+            this.total_syndicate_prizes.set(total - amount);
+
+            this.vm()
+                .call(&Call::new().value(amount), member, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(
+                this.vm(),
+                SyndicateShareClaimed {
+                    syndicateId: syndicate_id,
+                    member,
+                    amount,
+                },
+            );
+
+            Ok(amount)
+        })
+    }
+
+    /// Config admin gates the current or a future round to an allowlist; a zero root reopens it
+    pub fn set_round_allowlist_root(&mut self, round_id: U256, root: B256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+
+        let lottery_id = lottery_of(round_id);
+        let current_round = self.current_round_of(lottery_id)?;
+        if round_id < current_round {
+            return Err(Error::InvalidRoundState(InvalidRoundState {
+                roundId: round_id,
+                state: self.round_state(round_id) as u8,
+            }));
+        }
+        if round_id == current_round {
+            self.require_current_state(lottery_id, &[RoundState::Open, RoundState::Paused])?;
+        }
+
+        self.round_allowlist_root.insert(round_id, root);
+
+        log(
+            self.vm(),
+            AllowlistRootUpdated {
+                roundId: round_id,
+                root,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Owner starts the draw
+    pub fn start_draw(&mut self) -> Result<U256, Error> {
+        self.start_game_draw(U256::from(DEFAULT_LOTTERY_ID))
+    }
+
+    /// Operator starts the draw of a game's current round
+    pub fn start_game_draw(&mut self, lottery_id: U256) -> Result<U256, Error> {
+        self.nonreentrant(|this| {
+            this.vrf_requests_pausable.when_not_paused()?;
+            this.access.only_role(OPERATOR_ROLE.into())?;
+            let round = this.require_current_state(lottery_id, &[RoundState::Open])?;
+            
+            let players_count = this.round_players_count(round);
+            if players_count == 0 {
+                return Err(Error::NoPlayersInLottery(NoPlayersInLottery {}));
+            }
+
+            let prize_pool = this.games.get(lottery_id).prize_pool.get();
+            let min_prize = this.guaranteed_min_prize.get();
+            if prize_pool < min_prize {
+                return Err(Error::GuaranteedPrizeNotMet(GuaranteedPrizeNotMet {
+                    prizePool: prize_pool,
+                    required: min_prize,
+                }));
+            }
+            
+            let participants_root = this.participants_root(round);
+            this.round_participants_root
+                .insert(round, participants_root);
+
+            this.set_round_state(round, RoundState::Drawing);
+            
+            let callback_gas_limit = this.callback_gas_limit.get().try_into().unwrap_or(200000);
+            let request_confirmations = this.request_confirmations.get().try_into().unwrap_or(3);
+            let num_words = this.num_words.get().try_into().unwrap_or(1);
+            
+            let (request_id, _) = this.request_randomness_pay_in_native(
+                callback_gas_limit,
+                request_confirmations,
+                num_words,
+            )?;
+            
+            this.vrf_requests.insert(request_id, round);
+            
+            log(
+                this.vm(),
+                DrawStarted {
+                    lotteryId: lottery_id,
+                    requestId: request_id,
+                    playersCount: U256::from(players_count),
+                    participantsRoot: participants_root,
+                },
+            );
+            
+            log(
+                this.vm(),
+                VRFRequestSent {
+                    requestId: request_id,
+                    numWords: num_words,
+                },
+            );
+            
+            Ok(request_id)
+        })
+    }
+
+    /// Internal function to request randomness
+    fn request_randomness_pay_in_native(
+        &mut self,
+        callback_gas_limit: u32,
+        request_confirmations: u16,
+        num_words: u32,
+    ) -> Result<(U256, U256), Error> {
+        let external_vrf_wrapper_address = self.i_vrf_v2_plus_wrapper.get();
+        let external_vrf_wrapper = IVRFV2PlusWrapper::new(external_vrf_wrapper_address);
+
+        // Calculate request price
+        let request_price = external_vrf_wrapper
+            .calculate_request_price_native(&mut *self, callback_gas_limit, num_words)
+            .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+        // The fee comes out of the VRF reserve and surplus, never out of player funds
+        let budget = self.vrf_budget();
+        if request_price > budget {
+            return Err(Error::InsufficientVrfBudget(InsufficientVrfBudget {
+                price: request_price,
+                budget,
+            }));
+        }
+
+        let extra_args = get_extra_args_for_native_payment();
+
+        #[allow(deprecated)]
+        let config = OldCall::new().value(request_price);
+
+        // Request random words
+        let request_id = external_vrf_wrapper
+            .request_random_words_in_native(
+                config,
+                callback_gas_limit,
+                request_confirmations,
+                num_words,
+                extra_args,
+            )
+            .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+        self.vrf_request_wrapper
+            .insert(request_id, external_vrf_wrapper_address);
+
+        Ok((request_id, request_price))
+    }
+
+    /// Config admin proposes a new VRF wrapper, e.g. after Chainlink redeploys it, by
+    /// queueing `executeVrfWrapper(new_wrapper)` at least `VRF_WRAPPER_MIGRATION_DELAY`
+    /// (or the timelock delay, if longer) ahead. Returns the ETA.
+    pub fn propose_vrf_wrapper(&mut self, new_wrapper: Address) -> Result<U256, Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.check_vrf_wrapper(new_wrapper)?;
+
+        let delay = self
+            .timelock
+            .min_delay()
+            .max(U256::from(VRF_WRAPPER_MIGRATION_DELAY));
+        let eta = U256::from(self.vm().block_timestamp()) + delay;
+        self.timelock
+            .queue(vrf_wrapper_operation_id(new_wrapper), eta)?;
+        self.vrf_wrapper_proposal_eta.insert(new_wrapper, eta);
+
+        log(
+            self.vm(),
+            VRFWrapperProposed {
+                currentWrapper: self.i_vrf_v2_plus_wrapper.get(),
+                newWrapper: new_wrapper,
+                eta,
+            },
+        );
+
+        Ok(eta)
+    }
+
+    pub fn cancel_vrf_wrapper_proposal(&mut self, new_wrapper: Address) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelock
+            .cancel(vrf_wrapper_operation_id(new_wrapper))?;
+        self.vrf_wrapper_proposal_eta
+            .insert(new_wrapper, U256::ZERO);
+
+        log(
+            self.vm(),
+            VRFWrapperProposalCancelled {
+                newWrapper: new_wrapper,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Config admin switches to a proposed wrapper once its timelock is ready.
+    /// The old wrapper can still fulfil its in-flight requests for `VRF_WRAPPER_GRACE_PERIOD`.
+    /// Only a migration queued by `propose_vrf_wrapper` runs: one queued through
+    /// `queue_operation` would only have waited for the timelock delay.
+    pub fn execute_vrf_wrapper(&mut self, new_wrapper: Address) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.check_vrf_wrapper(new_wrapper)?;
+        let id = vrf_wrapper_operation_id(new_wrapper);
+        let eta = self.timelock.eta(id);
+        if !eta.is_zero() && eta != self.vrf_wrapper_proposal_eta.get(new_wrapper) {
+            return Err(Error::VRFWrapperNotProposed(VRFWrapperNotProposed {
+                wrapper: new_wrapper,
+            }));
+        }
+        self.timelock.execute(id)?;
+        self.vrf_wrapper_proposal_eta
+            .insert(new_wrapper, U256::ZERO);
+
+        let old_wrapper = self.i_vrf_v2_plus_wrapper.get();
+        let grace_end = U256::from(self.vm().block_timestamp() + VRF_WRAPPER_GRACE_PERIOD);
+        self.vrf_wrapper_retired_until
+            .insert(old_wrapper, grace_end);
+        self.vrf_wrapper_retired_until
+            .insert(new_wrapper, U256::ZERO);
+        self.i_vrf_v2_plus_wrapper.set(new_wrapper);
+
+        log(
+            self.vm(),
+            VRFWrapperUpdated {
+                oldWrapper: old_wrapper,
+                newWrapper: new_wrapper,
+                oldWrapperGraceEnd: grace_end,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Time a proposed wrapper can be executed; zero when it isn't proposed
+    pub fn get_vrf_wrapper_eta(&self, new_wrapper: Address) -> U256 {
+        self.timelock.eta(vrf_wrapper_operation_id(new_wrapper))
+    }
+
+    /// Wrapper that issued a request and is the only one allowed to fulfil it
+    pub fn get_request_wrapper(&self, request_id: U256) -> Address {
+        self.vrf_request_wrapper.get(request_id)
+    }
+
+    /// End of a replaced wrapper's fulfilment window; zero for the current wrapper
+    pub fn get_vrf_wrapper_grace_end(&self, wrapper: Address) -> U256 {
+        self.vrf_wrapper_retired_until.get(wrapper)
+    }
+
+    /// Callback called by VRF wrapper
+    pub fn raw_fulfill_random_words(
+        &mut self,
+        request_id: U256,
+        random_words: Vec<U256>,
+    ) -> Result<(), Error> {
+        self.only_request_wrapper(request_id)?;
+
+        log(
+            self.vm(),
+            VRFRequestFulfilled {
+                requestId: request_id,
+                randomWords: random_words.clone(),
+                payment: U256::ZERO,
+            },
+        );
+
+        self.fulfill_random_words(request_id, random_words)
+    }
+
+    /// Internal function to record the randomness. Winner selection and payouts happen
+    /// in `settle_round`, so they aren't bound by the VRF callback gas limit.
+    fn fulfill_random_words(
+        &mut self,
+        request_id: U256,
+        random_words: Vec<U256>,
+    ) -> Result<(), Error> {
+        if random_words.is_empty() {
+            return Ok(());
+        }
+
+        // Ignore stale requests, e.g. for a round cancelled while drawing
+        let round = self.vrf_requests.get(request_id);
+        let current_round = self.games.get(lottery_of(round)).current_round.get();
+        if round != current_round || self.round_state(round) != RoundState::Drawing {
+            return Ok(());
+        }
+
+        self.round_random_word.insert(round, random_words[0]);
+        self.set_round_state(round, RoundState::ReadyToSettle);
+
+        Ok(())
+    }
+
+    /// Anyone can settle a round once its randomness has been recorded:
+    /// selects the winner(s), opens the next round and then pays out
+    pub fn settle_round(&mut self, round_id: U256) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            this.require_round_state(round_id, &[RoundState::ReadyToSettle])?;
+
+            let random_word = this.round_random_word.get(round_id);
+            let players_count = this.round_players_count(round_id);
+
+            this.set_round_state(round_id, RoundState::Settled);
+
+            let lottery_id = lottery_of(round_id);
+            let prize_pool = this.games.get(lottery_id).prize_pool.get();
+
+            // No winner: the whole pot seeds the next round
+            if U256::from(players_count) < this.min_players_for_payout.get() {
+                this.roll_over_jackpot(lottery_id, prize_pool, true);
+                this.set_round_outcome(round_id, RoundOutcome::NoWinner);
+                this.reset_round(lottery_id);
+                return Ok(());
+            }
+
+            // Raffle mode: escrowed NFTs go to the default game's winners and only the
+            // round's ticket revenue to the treasury. The jackpot the round was seeded with
+            // and its sponsorships carry into the next pot.
+            if lottery_id == U256::from(DEFAULT_LOTTERY_ID)
+                && this.nft_prizes.len() > this.next_unassigned_prize.get().try_into().unwrap_or(0)
+            {
+                let carried = this.games.get(lottery_id).jackpot_reserve.get()
+                    + this.round_sponsorship.get(round_id);
+                let carried = carried.min(prize_pool);
+                let payout = prize_pool - carried;
+                this.roll_over_jackpot(lottery_id, carried, false);
+
+                this.award_nft_prizes(round_id, random_word)?;
+                this.set_round_outcome(round_id, RoundOutcome::Raffle);
+                this.reset_round(lottery_id);
+
+                let treasury = this.treasury.get();
+                if payout > U256::ZERO {
+                    this.vm()
+                        .call(&Call::new().value(payout), treasury, &[])
+                        .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+                }
+
+                log(
+                    this.vm(),
+                    TreasuryPaid {
+                        lotteryId: lottery_id,
+                        treasury,
+                        amount: payout,
+                    },
+                );
+
+                return Ok(());
+            }
+
+            let rollover =
+                prize_pool * this.jackpot_rollover_bps.get() / U256::from(BPS_DENOMINATOR);
+            let payout = prize_pool - rollover;
+            this.roll_over_jackpot(lottery_id, rollover, false);
+
+            // Select winner
+            let winner_index = select_ticket(random_word, players_count);
+            let winner = this
+                .players
+                .getter(round_id)
+                .get(winner_index)
+                .unwrap_or_default();
+
+            // Store last winner info
+            let mut game = this.games.setter(lottery_id);
+            game.last_winner.set(winner);
+            game.last_prize.set(payout);
+            this.round_winner.insert(round_id, winner);
+            this.set_round_outcome(round_id, RoundOutcome::Winner);
+
+            // Syndicate tickets credit the pool for pro-rata claims, others are paid directly
+            let syndicate_id = this.ticket_syndicate.getter(round_id).get(winner_index);
+            if syndicate_id.is_zero() {
+                this.record_player_win(winner, payout);
+            } else {
+                let prize = this.syndicates.get(syndicate_id).prize.get();
+                this.syndicates
+                    .setter(syndicate_id)
+                    .prize
+                    .set(prize + payout);
+                let total = this.total_syndicate_prizes.get();
+                this.total_syndicate_prizes.set(total + payout);
+
+                log(
+                    this.vm(),
+                    SyndicateWon {
+                        syndicateId: syndicate_id,
+                        roundId: round_id,
+                        prize: payout,
+                    },
+                );
+            }
+
+            // The next round is open before the winner is called
+            this.reset_round(lottery_id);
+            if syndicate_id.is_zero() {
+                this.pay_winner(winner, payout);
+            }
+
+            log(
+                this.vm(),
+                WinnerSelected {
+                    lotteryId: lottery_id,
+                    winner,
+                    prizeAmount: payout,
+                    randomWord: random_word,
+                },
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Internal function to pay a winner. A winner that can't receive ETH doesn't block
+    /// settlement: the prize is held as pending winnings for `claim_winnings`.
+    fn pay_winner(&mut self, winner: Address, amount: U256) {
+        if self
+            .vm()
+            .call(&Call::new().value(amount), winner, &[])
+            .is_ok()
+        {
+            return;
+        }
+
+        let pending = self.pending_winnings.get(winner);
+        self.pending_winnings.insert(winner, pending + amount);
+        let total_pending = self.total_pending_winnings.get();
+        self.total_pending_winnings.set(total_pending + amount);
+
+        log(self.vm(), WinningsPending { winner, amount });
+    }
+
+    /// Winners pull prizes that couldn't be transferred at draw time
+    pub fn claim_winnings(&mut self) -> Result<U256, Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            let winner = this.vm().msg_sender();
+            let amount = this.pending_winnings.get(winner);
+            if amount.is_zero() {
+                return Err(Error::NoPendingWinnings(NoPendingWinnings { winner }));
+            }
+
+            this.pending_winnings.insert(winner, U256::ZERO);
+            let total_pending = this.total_pending_winnings.get();
+            this.total_pending_winnings.set(total_pending - amount);
+
+            this.vm()
+                .call(&Call::new().value(amount), winner, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(this.vm(), WinningsClaimed { winner, amount });
+
+            Ok(amount)
+        })
+    }
+
+    /// Internal function to reset state for the game's next round, seeded with the
+    /// jackpot and whatever sponsors already put into that round
+    fn reset_round(&mut self, lottery_id: U256) {
+        let next_round = self.games.get(lottery_id).current_round.get() + U256::from(1);
+        self.games.setter(lottery_id).current_round.set(next_round);
+
+        let sponsorship = self.round_sponsorship.get(next_round);
+        let future_sponsorship = self.future_sponsorship.get();
+        self.future_sponsorship
+            .set(future_sponsorship - sponsorship);
+        let jackpot = self.games.get(lottery_id).jackpot_reserve.get();
+        self.set_prize_pool(lottery_id, jackpot + sponsorship);
+        self.set_round_state(next_round, RoundState::Open);
+        self.apply_next_entry_fee(lottery_id);
+    }
+
+    /// Operator cancels a game's current round. Entry fees become refundable through
+    /// `claim_refund`, while the jackpot and sponsorships carry into the next round.
+    pub fn cancel_round(&mut self, lottery_id: U256) -> Result<(), Error> {
+        self.access.only_role(OPERATOR_ROLE.into())?;
+        let round = self.require_current_state(
+            lottery_id,
+            &[RoundState::Open, RoundState::Paused, RoundState::Drawing],
+        )?;
+
+        let refunds = self.round_entry_total.get(round);
+        let total_refunds = self.total_refunds_owed.get();
+        self.total_refunds_owed.set(total_refunds + refunds);
+        let prize_pool = self.games.get(lottery_id).prize_pool.get();
+        self.games
+            .setter(lottery_id)
+            .jackpot_reserve
+            .set(prize_pool - refunds);
+
+        self.set_round_state(round, RoundState::Cancelled);
+        self.reset_round(lottery_id);
+
+        Ok(())
+    }
+
+    /// Operator pauses entries and draws for a game's current round
+    pub fn pause_round(&mut self, lottery_id: U256) -> Result<(), Error> {
+        self.access.only_role(OPERATOR_ROLE.into())?;
+        let round = self.require_current_state(lottery_id, &[RoundState::Open])?;
+        self.set_round_state(round, RoundState::Paused);
+        Ok(())
+    }
+
+    /// Operator reopens a paused round
+    pub fn resume_round(&mut self, lottery_id: U256) -> Result<(), Error> {
+        self.access.only_role(OPERATOR_ROLE.into())?;
+        let round = self.require_current_state(lottery_id, &[RoundState::Paused])?;
+        self.set_round_state(round, RoundState::Open);
+        Ok(())
+    }
+
+    /// Players of a cancelled round pull back what they paid to enter it
+    pub fn claim_refund(&mut self, round_id: U256) -> Result<U256, Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            this.require_round_state(round_id, &[RoundState::Cancelled])?;
+
+            let player = this.vm().msg_sender();
+            let amount = this.round_contributions.getter(round_id).get(player);
+            if amount.is_zero() {
+                return Err(Error::NoRefundOwed(NoRefundOwed {
+                    roundId: round_id,
+                    player,
+                }));
+            }
+
+            this.round_contributions
+                .setter(round_id)
+                .insert(player, U256::ZERO);
+            let total_refunds = this.total_refunds_owed.get();
+            this.total_refunds_owed.set(total_refunds - amount);
+
+            this.vm()
+                .call(&Call::new().value(amount), player, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(
+                this.vm(),
+                RefundClaimed {
+                    roundId: round_id,
+                    player,
+                    amount,
+                },
+            );
+
+            Ok(amount)
+        })
+    }
+
+    /// Anyone can add to the prize of the open round or of a future round
+    #[payable]
+    pub fn sponsor_round(&mut self, round_id: U256) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            let lottery_id = lottery_of(round_id);
+            let current_round = this.current_round_of(lottery_id)?;
+            let is_current = round_id == current_round;
+            if round_id < current_round {
+                return Err(Error::InvalidSponsorRound(InvalidSponsorRound {
+                    round: round_id,
+                }));
+            }
+            if is_current {
+                this.require_current_state(lottery_id, &[RoundState::Open, RoundState::Paused])?;
+            }
+
+            let amount = this.vm().msg_value();
+            let sponsored = this.round_sponsorship.get(round_id);
+            this.round_sponsorship.insert(round_id, sponsored + amount);
+
+            if is_current {
+                let current_pool = this.games.get(lottery_id).prize_pool.get();
+                this.set_prize_pool(lottery_id, current_pool + amount);
+            } else {
+                let future_sponsorship = this.future_sponsorship.get();
+                this.future_sponsorship.set(future_sponsorship + amount);
+            }
+
+            log(
+                this.vm(),
+                RoundSponsored {
+                    round: round_id,
+                    sponsor: this.vm().msg_sender(),
+                    amount,
+                },
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Config admin sets the prize a round must reach before its draw can start
+    pub fn set_guaranteed_min_prize(&mut self, min_prize: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setGuaranteedMinPrize", U256),
+            (min_prize,).abi_encode_params(),
+        )?;
+        let old_min_prize = self.guaranteed_min_prize.get();
+        self.guaranteed_min_prize.set(min_prize);
+
+        log(
+            self.vm(),
+            GuaranteedMinPrizeUpdated {
+                oldMinPrize: old_min_prize,
+                newMinPrize: min_prize,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Internal function to set aside part of the pot for the next round
+    fn roll_over_jackpot(&mut self, lottery_id: U256, amount: U256, no_winner: bool) {
+        self.games.setter(lottery_id).jackpot_reserve.set(amount);
+
+        log(
+            self.vm(),
+            JackpotRolledOver {
+                lotteryId: lottery_id,
+                amount,
+                noWinner: no_winner,
+            },
+        );
+    }
+
+    /// Config admin sets the share of every pot (in basis points) carried into the jackpot
+    pub fn set_jackpot_rollover_bps(&mut self, rollover_bps: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setJackpotRolloverBps", U256),
+            (rollover_bps,).abi_encode_params(),
+        )?;
+        if rollover_bps > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::InvalidRolloverBps(InvalidRolloverBps {
+                rolloverBps: rollover_bps,
+            }));
+        }
+
+        let old_bps = self.jackpot_rollover_bps.get();
+        self.jackpot_rollover_bps.set(rollover_bps);
+
+        log(
+            self.vm(),
+            JackpotRolloverUpdated {
+                oldRolloverBps: old_bps,
+                newRolloverBps: rollover_bps,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Config admin sets the entries a round needs to pay out; below it the whole pot rolls over
+    pub fn set_min_players_for_payout(&mut self, min_players: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setMinPlayersForPayout", U256),
+            (min_players,).abi_encode_params(),
+        )?;
+        let old_min_players = self.min_players_for_payout.get();
+        self.min_players_for_payout.set(min_players);
+
+        log(
+            self.vm(),
+            MinPlayersForPayoutUpdated {
+                oldMinPlayers: old_min_players,
+                newMinPlayers: min_players,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Internal function to assign the unassigned NFT prizes, at most one per address.
+    /// Prizes left over once every entrant has won one wait for the next raffle round.
+    fn award_nft_prizes(&mut self, round_id: U256, random_word: U256) -> Result<(), Error> {
+        let first_prize: usize = self.next_unassigned_prize.get().try_into().unwrap_or(0);
+        let prize_count = self.nft_prizes.len();
+
+        let mut winners: Vec<Address> = Vec::new();
+        let mut nonce = 0u64;
+        let mut next_prize = first_prize;
+
+        while next_prize < prize_count {
+            let Some(winner) = self.draw_nft_winner(round_id, random_word, &mut nonce, &winners)
+            else {
+                break;
+            };
+            winners.push(winner);
+            self.record_player_win(winner, U256::ZERO);
+
+            if let Some(mut prize) = self.nft_prizes.setter(next_prize) {
+                prize.winner.set(winner);
+            }
+
+            log(
+                self.vm(),
+                NftPrizeAwarded {
+                    lotteryId: lottery_of(round_id),
+                    prizeId: U256::from(next_prize),
+                    winner,
+                },
+            );
+            next_prize += 1;
+        }
+
+        self.next_unassigned_prize.set(U256::from(next_prize));
+
+        Ok(())
+    }
+
+    /// ERC-721 receiver hook: the owner escrows an NFT prize for the current round
+    #[selector(name = "onERC721Received")]
+    pub fn on_erc721_received(
+        &mut self,
+        operator: Address,
+        from: Address,
+        token_id: U256,
+        _data: AbiBytes,
+    ) -> Result<B32, Error> {
+        self.record_nft_prize(operator, from, token_id, U256::ZERO, false)?;
+        Ok(RECEIVER_FN_SELECTOR)
+    }
+
+    /// ERC-1155 receiver hook: the owner escrows a semi-fungible prize for the current round
+    #[selector(name = "onERC1155Received")]
+    pub fn on_erc1155_received(
+        &mut self,
+        operator: Address,
+        from: Address,
+        id: U256,
+        value: U256,
+        _data: AbiBytes,
+    ) -> Result<B32, Error> {
+        self.record_nft_prize(operator, from, id, value, true)?;
+        Ok(SINGLE_TRANSFER_FN_SELECTOR)
+    }
+
+    /// Internal function to record an incoming token as a prize
+    fn record_nft_prize(
+        &mut self,
+        operator: Address,
+        from: Address,
+        token_id: U256,
+        amount: U256,
+        is_erc1155: bool,
+    ) -> Result<(), Error> {
+        if !self.access.has_role(OPERATOR_ROLE.into(), operator)
+            && !self.access.has_role(OPERATOR_ROLE.into(), from)
+        {
+            return Err(Error::UnauthorizedPrizeDeposit(UnauthorizedPrizeDeposit {
+                from,
+            }));
+        }
+
+        self.require_current_state(
+            U256::from(DEFAULT_LOTTERY_ID),
+            &[RoundState::Open, RoundState::Paused],
+        )?;
+
+        let token = self.vm().msg_sender();
+        let prize_id = U256::from(self.nft_prizes.len());
+
+        let mut prize = self.nft_prizes.grow();
+        prize.token.set(token);
+        prize.token_id.set(token_id);
+        prize.amount.set(amount);
+        prize.is_erc1155.set(is_erc1155);
+
+        log(
+            self.vm(),
+            NftPrizeDeposited {
+                prizeId: prize_id,
+                token,
+                tokenId: token_id,
+                amount,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Winners pull their NFT prize
+    pub fn claim_nft_prize(&mut self, prize_id: U256) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            let index: usize = prize_id.try_into().unwrap_or(usize::MAX);
+            let caller = this.vm().msg_sender();
+
+            let Some(prize) = this.nft_prizes.get(index) else {
+                return Err(Error::NftPrizeNotFound(NftPrizeNotFound {
+                    prizeId: prize_id,
+                }));
+            };
+            if prize.winner.get() != caller {
+                return Err(Error::NotPrizeWinner(NotPrizeWinner {
+                    prizeId: prize_id,
+                    caller,
+                }));
+            }
+            if prize.claimed.get() {
+                return Err(Error::PrizeAlreadyClaimed(PrizeAlreadyClaimed {
+                    prizeId: prize_id,
+                }));
+            }
+
+            let token = prize.token.get();
+            let token_id = prize.token_id.get();
+            let amount = prize.amount.get();
+            let is_erc1155 = prize.is_erc1155.get();
+
+            if let Some(mut prize) = this.nft_prizes.setter(index) {
+                prize.claimed.set(true);
+            }
+
+            let contract_address = this.vm().contract_address();
+
+            #[allow(deprecated)]
+            let config = OldCall::new();
+
+            if is_erc1155 {
+                IERC1155::new(token)
+                    .safe_transfer_from(
+                        config,
+                        contract_address,
+                        caller,
+                        token_id,
+                        amount,
+                        Bytes::new(),
+                    )
+                    .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+            } else {
+                IERC721::new(token)
+                    .safe_transfer_from(config, contract_address, caller, token_id)
+                    .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+            }
+
+            log(
+                this.vm(),
+                NftPrizeClaimed {
+                    prizeId: prize_id,
+                    winner: caller,
+                },
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Guardian flips one of the `PauseScope` switches. Entries stop ticket sales in
+    /// every game; VRF requests stop `start_draw` while requests already in flight are
+    /// still recorded; payouts stop every outgoing transfer.
+    pub fn set_paused(&mut self, scope: u8, paused: bool) -> Result<(), Error> {
+        self.access.only_role(GUARDIAN_ROLE.into())?;
+        let pausable = match PauseScope::try_from(scope) {
+            Ok(PauseScope::Entries) => &mut self.entries_pausable,
+            Ok(PauseScope::VrfRequests) => &mut self.vrf_requests_pausable,
+            Ok(PauseScope::Payouts) => &mut self.payouts_pausable,
+            Err(_) => return Err(Error::InvalidPauseScope(InvalidPauseScope { scope })),
+        };
+        if paused {
+            pausable.pause()?;
+        } else {
+            pausable.unpause()?;
+        }
+
+        log(
+            self.vm(),
+            PauseUpdated {
+                scope,
+                paused,
+                guardian: self.vm().msg_sender(),
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn is_paused(&self, scope: u8) -> Result<bool, Error> {
+        let pausable = match PauseScope::try_from(scope) {
+            Ok(PauseScope::Entries) => &self.entries_pausable,
+            Ok(PauseScope::VrfRequests) => &self.vrf_requests_pausable,
+            Ok(PauseScope::Payouts) => &self.payouts_pausable,
+            Err(_) => return Err(Error::InvalidPauseScope(InvalidPauseScope { scope })),
+        };
+        Ok(pausable.paused())
+    }
+
+    /// Treasurer sets where raffle-mode ticket revenue is sent
+    pub fn set_treasury(&mut self, new_treasury: Address) -> Result<(), Error> {
+        self.access.only_role(TREASURER_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setTreasury", Address),
+            (new_treasury,).abi_encode_params(),
+        )?;
+        let old_treasury = self.treasury.get();
+        self.treasury.set(new_treasury);
+
+        log(
+            self.vm(),
+            TreasuryUpdated {
+                oldTreasury: old_treasury,
+                newTreasury: new_treasury,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Config admin changes the default game's entry fee from its next round on.
+    /// Timelocked: queue `setEntryFee(uint256)` first.
+    pub fn set_entry_fee(&mut self, new_fee: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setEntryFee", U256),
+            (new_fee,).abi_encode_params(),
+        )?;
+        self.schedule_entry_fee(U256::from(DEFAULT_LOTTERY_ID), new_fee)
+    }
+
+    /// Config admin changes one game's entry fee from its next round on.
+    /// Timelocked: queue `setGameEntryFee(uint256,uint256)` first.
+    pub fn set_game_entry_fee(&mut self, lottery_id: U256, new_fee: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setGameEntryFee", U256, U256),
+            (lottery_id, new_fee).abi_encode_params(),
+        )?;
+        self.schedule_entry_fee(lottery_id, new_fee)
+    }
+
+    /// Returns (scheduled, fee) for the fee a game switches to when its next round opens
+    pub fn get_next_entry_fee(&self, lottery_id: U256) -> (bool, U256) {
+        let game = self.games.get(lottery_id);
+        (game.has_next_entry_fee.get(), game.next_entry_fee.get())
+    }
+
+    /// Config admin or treasurer queues a timelocked operation, identified by
+    /// `hash_operation(selector, params)` of the call it authorizes
+    pub fn queue_operation(&mut self, id: B256, eta: U256) -> Result<(), Error> {
+        self.only_timelock_proposer()?;
+        Ok(self.timelock.queue(id, eta)?)
+    }
+
+    pub fn cancel_operation(&mut self, id: B256) -> Result<(), Error> {
+        self.only_timelock_proposer()?;
+        Ok(self.timelock.cancel(id)?)
+    }
+
+    /// Operation id of calling `selector` with ABI-encoded `params`
+    pub fn hash_operation(&self, selector: B32, params: AbiBytes) -> B256 {
+        Timelock::operation_id(selector.0, &params)
+    }
+
+    /// ETA of a queued operation; zero when not queued
+    pub fn get_operation_eta(&self, id: B256) -> U256 {
+        self.timelock.eta(id)
+    }
+
+    /// Config admin changes the timelock delay, itself through the timelock
+    pub fn set_timelock_delay(&mut self, delay: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setTimelockDelay", U256),
+            (delay,).abi_encode_params(),
+        )?;
+        self.timelock.set_min_delay(delay);
+        Ok(())
+    }
+
+    pub fn get_timelock_delay(&self) -> U256 {
+        self.timelock.min_delay()
+    }
+
+    /// View functions
+    pub fn get_entry_fee(&self) -> U256 {
+        self.games
+            .get(U256::from(DEFAULT_LOTTERY_ID))
+            .entry_fee
+            .get()
+    }
+
+    pub fn get_last_winner(&self) -> Address {
+        self.games
+            .get(U256::from(DEFAULT_LOTTERY_ID))
+            .last_winner
+            .get()
+    }
+    
+    pub fn get_last_prize(&self) -> U256 {
+        self.games
+            .get(U256::from(DEFAULT_LOTTERY_ID))
+            .last_prize
+            .get()
+    }
+
+    pub fn is_lottery_open(&self) -> bool {
+        self.round_state(self.default_round()) == RoundState::Open
+    }
+
+    pub fn get_lottery_count(&self) -> U256 {
+        self.lottery_count.get()
+    }
+
+    /// Returns (entry fee, current round id, prize pool, jackpot, last winner, last prize)
+    pub fn get_game(&self, lottery_id: U256) -> (U256, U256, U256, U256, Address, U256) {
+        let game = self.games.get(lottery_id);
+        (
+            game.entry_fee.get(),
+            game.current_round.get(),
+            game.prize_pool.get(),
+            game.jackpot_reserve.get(),
+            game.last_winner.get(),
+            game.last_prize.get(),
+        )
+    }
+
+    /// Game a round id belongs to
+    pub fn get_round_lottery(&self, round_id: U256) -> U256 {
+        lottery_of(round_id)
+    }
+
+    pub fn get_round_players_count(&self, round_id: U256) -> U256 {
+        U256::from(self.round_players_count(round_id))
+    }
+
+    /// Player holding the `index`-th ticket of a round
+    pub fn get_round_player(&self, round_id: U256, index: U256) -> Address {
+        self.players
+            .getter(round_id)
+            .get(index)
+            .unwrap_or(Address::ZERO)
+    }
+
+    /// Returns the `RoundOutcome` of a round as its `uint8` value
+    pub fn get_round_outcome(&self, round_id: U256) -> u8 {
+        self.round_outcome(round_id) as u8
+    }
+
+    pub fn get_round_state(&self, round_id: U256) -> u8 {
+        self.round_state(round_id) as u8
+    }
+
+    pub fn get_round_contribution(&self, round_id: U256, player: Address) -> U256 {
+        self.round_contributions.getter(round_id).get(player)
+    }
+
+    pub fn get_players_count(&self) -> U256 {
+        U256::from(self.round_players_count(self.default_round()))
+    }
+
+    /// Player holding the `index`-th ticket of the current round
+    pub fn get_player(&self, index: U256) -> Address {
+        self.get_round_player(self.default_round(), index)
+    }
+
+    pub fn get_round_random_word(&self, round_id: U256) -> U256 {
+        self.round_random_word.get(round_id)
+    }
+
+    /// Merkle root of a round's `(ticket_index, player)` leaves, frozen by `start_draw`.
+    /// Leaves are `keccak256(abi.encodePacked(uint256 ticketIndex, address player))` with
+    /// ticket indices counted from 0 within the round; empty leaves are zero.
+    pub fn get_round_participants_root(&self, round_id: U256) -> B256 {
+        self.round_participants_root.get(round_id)
+    }
+
+    /// Live participants root of the current round
+    pub fn get_current_participants_root(&self) -> B256 {
+        self.participants_root(self.default_round())
+    }
+
+    pub fn get_round_allowlist_root(&self, round_id: U256) -> B256 {
+        self.round_allowlist_root.get(round_id)
+    }
+
+    /// Tickets a player has bought through `enter_lottery_with_proof` in a gated round
+    pub fn get_allowlist_entries(&self, round_id: U256, player: Address) -> U256 {
+        self.round_allowlist_entries.getter(round_id).get(player)
+    }
+
+    /// Next nonce `enter_with_signature` expects from a player
+    pub fn nonces(&self, player: Address) -> U256 {
+        INonces::nonces(&self.nonces, player)
+    }
+
+    /// Returns (rounds played, tickets bought, wins, total winnings). Syndicate
+    /// tickets count for the contributing member; their wins count when claimed.
+    pub fn get_player_stats(&self, player: Address) -> (U256, U256, U256, U256) {
+        let stats = self.player_stats.get(player);
+        (
+            stats.rounds_played.get(),
+            stats.tickets_bought.get(),
+            stats.wins.get(),
+            stats.total_winnings.get(),
+        )
+    }
+
+    pub fn get_player_tickets(&self, round_id: U256, player: Address) -> U256 {
+        self.round_player_tickets.getter(round_id).get(player)
+    }
+
+    pub fn get_referrer(&self, player: Address) -> Address {
+        self.referrer_of.get(player)
+    }
+
+    /// Returns (players referred, total rewards earned)
+    pub fn get_referral_stats(&self, referrer: Address) -> (U256, U256) {
+        (
+            self.referral_count.get(referrer),
+            self.referral_earnings.get(referrer),
+        )
+    }
+
+    pub fn get_referral_balance(&self, referrer: Address) -> U256 {
+        self.referral_balances.get(referrer)
+    }
+
+    pub fn get_referral_bps(&self) -> U256 {
+        self.referral_bps.get()
+    }
+
+    pub fn get_syndicate_count(&self) -> U256 {
+        self.syndicate_count.get()
+    }
+
+    /// Returns (creator, round id, total contributed, tickets, prize)
+    pub fn get_syndicate(&self, syndicate_id: U256) -> (Address, U256, U256, U256, U256) {
+        let syndicate = self.syndicates.get(syndicate_id);
+        (
+            syndicate.creator.get(),
+            syndicate.round_id.get(),
+            syndicate.total_contributed.get(),
+            syndicate.tickets.get(),
+            syndicate.prize.get(),
+        )
+    }
+
+    pub fn get_syndicate_contribution(&self, syndicate_id: U256, member: Address) -> U256 {
+        self.syndicate_contributions
+            .getter(syndicate_id)
+            .get(member)
+    }
+
+    /// Unclaimed share of a syndicate's prize owed to `member`
+    pub fn get_syndicate_share(&self, syndicate_id: U256, member: Address) -> U256 {
+        self.syndicate_share(syndicate_id, member)
+    }
+
+    /// Syndicate owning a round's `ticket_index`-th ticket, zero for individual tickets
+    pub fn get_ticket_syndicate(&self, round_id: U256, ticket_index: U256) -> U256 {
+        self.ticket_syndicate.getter(round_id).get(ticket_index)
+    }
+
+    /// Returns (rounds remaining, tickets per round, price per round, last enrolled round,
+    /// lottery id)
+    pub fn get_subscription(&self, player: Address) -> (U256, U256, U256, U256, U256) {
+        let subscription = self.subscriptions.get(player);
+        (
+            subscription.rounds_remaining.get(),
+            subscription.tickets_per_round.get(),
+            subscription.price_per_round.get(),
+            subscription.last_enrolled_round.get(),
+            subscription
+                .lottery_id
+                .get()
+                .max(U256::from(DEFAULT_LOTTERY_ID)),
+        )
+    }
+
+    pub fn get_subscribers_count(&self) -> U256 {
+        U256::from(self.subscribers.len())
+    }
+
+    /// Subscribers still to be visited by `enroll_subscribers` in a round
+    pub fn get_pending_enrolments(&self, round_id: U256) -> U256 {
+        U256::from(self.subscribers.len()).saturating_sub(self.round_enrolment_cursor.get(round_id))
+    }
+
+    pub fn get_prepaid_balance(&self, player: Address) -> U256 {
+        self.prepaid_balance.get(player)
+    }
+
+    pub fn domain_separator(&self) -> B256 {
+        self.domain_separator_v4()
+    }
+
+    pub fn get_round_winner(&self, round_id: U256) -> Address {
+        self.round_winner.get(round_id)
+    }
+
+    /// Recomputes a settled round's winner from its stored random word and tickets,
+    /// with the same selection `settle_round` uses.
+    /// Returns (`RoundOutcome`, ticket index within the round, winner, whether it matches
+    /// the recorded winner). Raffle and no-winner rounds have no winner to recompute, so
+    /// they return their outcome with a zero index and winner.
+    pub fn verify_round(&self, round_id: U256) -> Result<(u8, U256, Address, bool), Error> {
+        self.require_round_state(round_id, &[RoundState::Settled])?;
+
+        let outcome = self.round_outcome(round_id);
+        if matches!(outcome, RoundOutcome::NoWinner | RoundOutcome::Raffle) {
+            let no_winner_recorded = self.round_winner.get(round_id).is_zero();
+            return Ok((outcome as u8, U256::ZERO, Address::ZERO, no_winner_recorded));
+        }
+
+        let players_count = self.round_players_count(round_id);
+        if players_count == 0 {
+            return Err(Error::NoPlayersInLottery(NoPlayersInLottery {}));
+        }
+
+        let index = select_ticket(self.round_random_word.get(round_id), players_count);
+        let winner = self.get_round_player(round_id, index);
+
+        Ok((
+            outcome as u8,
+            index,
+            winner,
+            winner == self.round_winner.get(round_id),
+        ))
+    }
+
+    pub fn get_prize_pool(&self) -> U256 {
+        self.games
+            .get(U256::from(DEFAULT_LOTTERY_ID))
+            .prize_pool
+            .get()
+    }
+
+    pub fn get_current_round(&self) -> U256 {
+        self.default_round()
+    }
+
+    pub fn get_round_sponsorship(&self, round_id: U256) -> U256 {
+        self.round_sponsorship.get(round_id)
+    }
+
+    pub fn get_guaranteed_min_prize(&self) -> U256 {
+        self.guaranteed_min_prize.get()
+    }
+
+    /// Part of the current prize pool carried over from previous rounds
+    pub fn get_jackpot(&self) -> U256 {
+        self.games
+            .get(U256::from(DEFAULT_LOTTERY_ID))
+            .jackpot_reserve
+            .get()
+    }
+
+    pub fn get_jackpot_rollover_bps(&self) -> U256 {
+        self.jackpot_rollover_bps.get()
+    }
+
+    pub fn get_min_players_for_payout(&self) -> U256 {
+        self.min_players_for_payout.get()
+    }
+
+    pub fn get_treasury(&self) -> Address {
+        self.treasury.get()
+    }
+
+    pub fn get_nft_prize_count(&self) -> U256 {
+        U256::from(self.nft_prizes.len())
+    }
+
+    /// Returns (token, tokenId, amount, isErc1155, winner, claimed)
+    pub fn get_nft_prize(&self, prize_id: U256) -> (Address, U256, U256, bool, Address, bool) {
+        let idx: usize = prize_id.try_into().unwrap_or(usize::MAX);
+        match self.nft_prizes.get(idx) {
+            Some(prize) => (
+                prize.token.get(),
+                prize.token_id.get(),
+                prize.amount.get(),
+                prize.is_erc1155.get(),
+                prize.winner.get(),
+                prize.claimed.get(),
+            ),
+            None => (
+                Address::ZERO,
+                U256::ZERO,
+                U256::ZERO,
+                false,
+                Address::ZERO,
+                false,
+            ),
+        }
+    }
+
+    pub fn owner(&self) -> Address {
+        self.ownable.owner()
+    }
+
+    pub fn pending_owner(&self) -> Address {
+        self.ownable.pending_owner()
+    }
+
+    /// Owner nominates a new owner, who must call `accept_ownership`. Nominating
+    /// again overwrites the pending owner; nominating the zero address cancels.
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Error> {
+        Ok(self.ownable.transfer_ownership(new_owner)?)
+    }
+
+    /// Pending owner takes over, along with the `OWNER_ROLES` the previous owner loses.
+    /// Roles granted to other accounts stay with their holders.
+    pub fn accept_ownership(&mut self) -> Result<(), Error> {
+        let previous_owner = self.ownable.owner();
+        self.ownable.accept_ownership()?;
+
+        let new_owner = self.ownable.owner();
+        for role in OWNER_ROLES {
+            self.access._revoke_role(role.into(), previous_owner);
+            self.access._grant_role(role.into(), new_owner);
+        }
+
+        Ok(())
+    }
+
+    /// Owner gives up ownership and its `OWNER_ROLES`. Roles granted to other
+    /// accounts stay with their holders.
+    pub fn renounce_ownership(&mut self) -> Result<(), Error> {
+        let owner = self.ownable.owner();
+        self.ownable.renounce_ownership()?;
+        for role in OWNER_ROLES {
+            self.access._revoke_role(role.into(), owner);
+        }
+        Ok(())
+    }
+
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        self.access.has_role(role, account)
+    }
+
+    pub fn get_role_admin(&self, role: B256) -> B256 {
+        self.access.get_role_admin(role)
+    }
+
+    /// Holders of the role's admin role (the default admin for every role here)
+    /// grant and revoke it; emits `RoleGranted` / `RoleRevoked`
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), Error> {
+        Ok(self.access.grant_role(role, account)?)
+    }
+
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), Error> {
+        Ok(self.access.revoke_role(role, account)?)
+    }
+
+    /// Caller drops one of its own roles; `confirmation` must be the caller
+    pub fn renounce_role(&mut self, role: B256, confirmation: Address) -> Result<(), Error> {
+        Ok(self.access.renounce_role(role, confirmation)?)
+    }
+
+    pub fn config_admin_role(&self) -> B256 {
+        CONFIG_ADMIN_ROLE.into()
+    }
+
+    pub fn operator_role(&self) -> B256 {
+        OPERATOR_ROLE.into()
+    }
+
+    pub fn treasurer_role(&self) -> B256 {
+        TREASURER_ROLE.into()
+    }
+
+    pub fn guardian_role(&self) -> B256 {
+        GUARDIAN_ROLE.into()
+    }
+
+    pub fn i_vrf_v2_plus_wrapper(&self) -> Address {
+        self.i_vrf_v2_plus_wrapper.get()
+    }
+
+    pub fn callback_gas_limit(&self) -> U32 {
+        self.callback_gas_limit.get()
+    }
+
+    pub fn request_confirmations(&self) -> U16 {
+        self.request_confirmations.get()
+    }
+
+    pub fn num_words(&self) -> U32 {
+        self.num_words.get()
+    }
+
+    /// Balance not owed to players, sponsors or the next VRF request
+    pub fn withdrawable_surplus(&self) -> U256 {
+        self.vrf_budget()
+            .saturating_sub(self.vrf_budget_reserve.get())
+    }
+
+    pub fn get_pending_winnings(&self, winner: Address) -> U256 {
+        self.pending_winnings.get(winner)
+    }
+
+    pub fn get_vrf_budget_reserve(&self) -> U256 {
+        self.vrf_budget_reserve.get()
+    }
+
+    /// Config admin sets the balance kept back from withdrawals to pay for VRF requests
+    pub fn set_vrf_budget_reserve(&mut self, reserve: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelocked(
+            function_selector!("setVrfBudgetReserve", U256),
+            (reserve,).abi_encode_params(),
+        )?;
+        let old_reserve = self.vrf_budget_reserve.get();
+        self.vrf_budget_reserve.set(reserve);
+
+        log(
+            self.vm(),
+            VRFBudgetReserveUpdated {
+                oldReserve: old_reserve,
+                newReserve: reserve,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Treasurer withdraws native tokens, limited to the withdrawable surplus
+    pub fn withdraw_native(&mut self, amount: U256) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            this.access.only_role(TREASURER_ROLE.into())?;
+            this.timelocked(
+                function_selector!("withdrawNative", U256),
+                (amount,).abi_encode_params(),
+            )?;
+
+            let available = this.withdrawable_surplus();
+            if amount > available {
+                return Err(Error::InsufficientSurplus(InsufficientSurplus {
+                    requested: amount,
+                    available,
+                }));
+            }
+
+            let treasurer = this.vm().msg_sender();
+            this.vm()
+                .call(&Call::new().value(amount), treasurer, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(
+                this.vm(),
+                Withdrawn {
+                    to: treasurer,
+                    amount,
+                },
+            );
+
+            Ok(())
+        })
+    }
+
+    /// Receive function
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        log(
+            self.vm(),
+            Received {
+                sender: self.vm().msg_sender(),
+                value: self.vm().msg_value(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Fund contract for VRF payments
+    #[payable]
+    pub fn fund_contract(&mut self) -> Result<(), Vec<u8>> {
+        Ok(())
+    }
+}
+
+impl Lottery {
+    /// Records `tickets` paid tickets for `player` in the current round
+    fn enter(
+        &mut self,
+        round_id: U256,
+        player: Address,
+        tickets: U256,
+        value: U256,
+    ) -> Result<(), Error> {
+        self.entries_pausable.when_not_paused()?;
+        if tickets.is_zero() {
+            return Err(Error::InvalidTicketCount(InvalidTicketCount { tickets }));
+        }
+
+        let required_fee = self.games.get(lottery_of(round_id)).entry_fee.get() * tickets;
+        if value < required_fee {
+            return Err(Error::InsufficientEntryFee(InsufficientEntryFee {
+                sent: value,
+                required: required_fee,
+            }));
+        }
+
+        self.add_tickets(round_id, player, tickets, value);
+        Ok(())
+    }
+
+    /// Enrols one subscriber into `round_id` at their locked price; returns
+    /// false when the pass is used up or already covers this round
+    fn enroll_subscriber(&mut self, round_id: U256, player: Address) -> Result<bool, Error> {
+        let subscription = self.subscriptions.get(player);
+        let rounds = subscription.rounds_remaining.get();
+        // Passes bought before games were tracked have no id and belong to the default game
+        let lottery_id = subscription
+            .lottery_id
+            .get()
+            .max(U256::from(DEFAULT_LOTTERY_ID));
+        if rounds.is_zero()
+            || subscription.last_enrolled_round.get() == round_id
+            || lottery_id != lottery_of(round_id)
+        {
+            return Ok(false);
+        }
+        let tickets = subscription.tickets_per_round.get();
+        let price = subscription.price_per_round.get();
+
+        let mut subscription = self.subscriptions.setter(player);
+        subscription.rounds_remaining.set(rounds - U256::from(1));
+        subscription.last_enrolled_round.set(round_id);
+        self.debit_subscription_balance(price)?;
+
+        self.add_tickets(round_id, player, tickets, price);
+        Ok(true)
+    }
+
+    /// Takes `amount` out of the prepaid season-pass balance, refusing to go below zero
+    fn debit_subscription_balance(&mut self, amount: U256) -> Result<(), Error> {
+        let balance = self.total_subscription_balance.get();
+        let Some(remaining) = balance.checked_sub(amount) else {
+            return Err(Error::InsufficientSubscriptionBalance(
+                InsufficientSubscriptionBalance {
+                    balance,
+                    required: amount,
+                },
+            ));
+        };
+        self.total_subscription_balance.set(remaining);
+        Ok(())
+    }
+
+    /// Records paid tickets and credits `value` to the round's pool
+    fn add_tickets(&mut self, round_id: U256, player: Address, tickets: U256, value: U256) {
+        let mut ticket = U256::ZERO;
+        while ticket < tickets {
+            self.record_ticket(round_id, player);
+            ticket += U256::from(1);
+        }
+        self.record_player_tickets(round_id, player, tickets);
+
+        self.credit_entry(round_id, player, value);
+    }
+
+    /// Bumps a player's ticket counters, counting the round once
+    fn record_player_tickets(&mut self, round_id: U256, player: Address, tickets: U256) {
+        let round_tickets = self.round_player_tickets.getter(round_id).get(player);
+        self.round_player_tickets
+            .setter(round_id)
+            .insert(player, round_tickets + tickets);
+
+        let stats = self.player_stats.get(player);
+        let rounds_played = stats.rounds_played.get();
+        let tickets_bought = stats.tickets_bought.get();
+        let mut stats = self.player_stats.setter(player);
+        if round_tickets.is_zero() {
+            stats.rounds_played.set(rounds_played + U256::from(1));
+        }
+        stats.tickets_bought.set(tickets_bought + tickets);
+    }
+
+    /// Counts a win; NFT prizes count with a zero amount
+    fn record_player_win(&mut self, player: Address, amount: U256) {
+        let stats = self.player_stats.get(player);
+        let wins = stats.wins.get();
+        let total_winnings = stats.total_winnings.get();
+        let mut stats = self.player_stats.setter(player);
+        stats.wins.set(wins + U256::from(1));
+        stats.total_winnings.set(total_winnings + amount);
+    }
+
+    /// Credits `value` to the round's pool and to `player`'s refundable contribution
+    fn credit_entry(&mut self, round_id: U256, player: Address, value: U256) {
+        // Add to prize pool
+        let lottery_id = lottery_of(round_id);
+        let current_pool = self.games.get(lottery_id).prize_pool.get();
+        self.set_prize_pool(lottery_id, current_pool + value);
+
+        let entry_total = self.round_entry_total.get(round_id);
+        self.round_entry_total.insert(round_id, entry_total + value);
+        let contribution = self.round_contributions.getter(round_id).get(player);
+        self.round_contributions
+            .setter(round_id)
+            .insert(player, contribution + value);
+
+        log(
+            self.vm(),
+            LotteryEntered {
+                player,
+                lotteryId: lottery_id,
+                entryFee: value,
+            },
+        );
+    }
+
+    fn syndicate_share(&self, syndicate_id: U256, member: Address) -> U256 {
+        if self.syndicate_claimed.getter(syndicate_id).get(member) {
+            return U256::ZERO;
+        }
+        let syndicate = self.syndicates.get(syndicate_id);
+        let total_contributed = syndicate.total_contributed.get();
+        if total_contributed.is_zero() {
+            return U256::ZERO;
+        }
+        syndicate.prize.get()
+            * self
+                .syndicate_contributions
+                .getter(syndicate_id)
+                .get(member)
+            / total_contributed
+    }
+
+    /// Internal function to draw a ticket whose holder hasn't won yet: a few random
+    /// re-rolls, then a scan onwards from the last pick. `None` once everyone has won.
+    fn draw_nft_winner(
+        &self,
+        round_id: U256,
+        random_word: U256,
+        nonce: &mut u64,
+        winners: &[Address],
+    ) -> Option<Address> {
+        let players_count = self.round_players_count(round_id);
+        let mut index = U256::ZERO;
+        for _ in 0..MAX_PRIZE_DRAW_ATTEMPTS {
+            index = select_ticket(expand_random_word(random_word, *nonce), players_count);
+            *nonce += 1;
+            let holder = self.ticket_holder(round_id, index);
+            if !winners.contains(&holder) {
+                return Some(holder);
+            }
+        }
+
+        let tickets = U256::from(players_count);
+        (1..players_count)
+            .map(|offset| (index + U256::from(offset)) % tickets)
+            .map(|ticket| self.ticket_holder(round_id, ticket))
+            .find(|holder| !winners.contains(holder))
+    }
+
+    /// Internal function to get who holds a ticket's NFT prize. An NFT can't be split,
+    /// so a syndicate's creator holds it for the group.
+    fn ticket_holder(&self, round_id: U256, index: U256) -> Address {
+        let syndicate_id = self.ticket_syndicate.getter(round_id).get(index);
+        if syndicate_id.is_zero() {
+            self.players
+                .getter(round_id)
+                .get(index)
+                .unwrap_or(Address::ZERO)
+        } else {
+            self.syndicates.get(syndicate_id).creator.get()
+        }
+    }
+
+    /// Internal function to get the balance not owed to players or sponsors, which
+    /// VRF requests may spend
+    fn vrf_budget(&self) -> U256 {
+        let balance = self.vm().balance(self.vm().contract_address());
+        let committed = self.current_prize_pool.get()
+            + self.future_sponsorship.get()
+            + self.total_pending_winnings.get()
+            + self.total_refunds_owed.get()
+            + self.total_prepaid.get()
+            + self.total_subscription_balance.get()
+            + self.total_syndicate_prizes.get()
+            + self.total_referral_balances.get();
+        balance.saturating_sub(committed)
+    }
+
+    /// Shared by the constructor and `initialize`
+    fn init(
+        &mut self,
+        vrf_v2_plus_wrapper: Address,
+        entry_fee: U256,
+        owner: Address,
+    ) -> Result<(), Error> {
+        if self.initialized.get() {
+            return Err(Error::InvalidInitialization(
+                uups_upgradeable::InvalidInitialization {},
+            ));
+        }
+        self.initialized.set(true);
+
+        self.ownable.constructor(owner)?;
+        self.i_vrf_v2_plus_wrapper.set(vrf_v2_plus_wrapper);
+        self.current_prize_pool.set(U256::ZERO);
+        self.treasury.set(owner);
+        for role in OWNER_ROLES {
+            self.access._grant_role(role.into(), owner);
+        }
+        self.timelock
+            .set_min_delay(U256::from(DEFAULT_TIMELOCK_DELAY));
+        self.open_lottery(entry_fee);
+
+        self.callback_gas_limit.set(U32::from(200000));
+        self.request_confirmations.set(U16::from(3));
+        self.num_words.set(U32::from(1));
+
+        Ok(())
+    }
+
+    /// Runs `f` holding the reentrancy guard
+    fn nonreentrant<T, E: From<reentrancy_guard::Error>>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.reentrancy.enter()?;
+        let result = f(self);
+        self.reentrancy.exit();
+        result
+    }
+
+    /// Consumes the queued operation for this call; errors unless it is ready
+    fn timelocked(&mut self, selector: [u8; 4], params: Vec<u8>) -> Result<(), Error> {
+        let id = Timelock::operation_id(selector, &params);
+        Ok(self.timelock.execute(id)?)
+    }
+
+    /// A wrapper can be migrated to unless it is zero or already in use
+    fn check_vrf_wrapper(&self, wrapper: Address) -> Result<(), Error> {
+        if wrapper.is_zero() || wrapper == self.i_vrf_v2_plus_wrapper.get() {
+            return Err(Error::InvalidVRFWrapper(InvalidVRFWrapper { wrapper }));
+        }
+        Ok(())
+    }
+
+    fn only_timelock_proposer(&self) -> Result<(), Error> {
+        let caller = self.vm().msg_sender();
+        if !self.access.has_role(CONFIG_ADMIN_ROLE.into(), caller)
+            && !self.access.has_role(TREASURER_ROLE.into(), caller)
+        {
+            return Err(Error::AccessControlUnauthorizedAccount(
+                control::AccessControlUnauthorizedAccount {
+                    account: caller,
+                    needed_role: CONFIG_ADMIN_ROLE.into(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Stages a new entry fee; the running round keeps the fee its players paid
+    fn schedule_entry_fee(&mut self, lottery_id: U256, new_fee: U256) -> Result<(), Error> {
+        let round = self.current_round_of(lottery_id)?;
+        let mut game = self.games.setter(lottery_id);
+        game.next_entry_fee.set(new_fee);
+        game.has_next_entry_fee.set(true);
+
+        log(
+            self.vm(),
+            GameEntryFeeScheduled {
+                lotteryId: lottery_id,
+                newFee: new_fee,
+                effectiveRoundId: round + U256::from(1),
+            },
+        );
+
+        Ok(())
+    }
+
+    fn apply_next_entry_fee(&mut self, lottery_id: U256) {
+        let mut game = self.games.setter(lottery_id);
+        if !game.has_next_entry_fee.get() {
+            return;
+        }
+        let old_fee = game.entry_fee.get();
+        let new_fee = game.next_entry_fee.get();
+        game.entry_fee.set(new_fee);
+        game.next_entry_fee.set(U256::ZERO);
+        game.has_next_entry_fee.set(false);
+
+        log(
+            self.vm(),
+            GameEntryFeeUpdated {
+                lotteryId: lottery_id,
+                oldFee: old_fee,
+                newFee: new_fee,
+            },
+        );
+        if lottery_id == U256::from(DEFAULT_LOTTERY_ID) {
+            log(
+                self.vm(),
+                EntryFeeUpdated {
+                    oldFee: old_fee,
+                    newFee: new_fee,
+                },
+            );
+        }
+    }
+
+    /// Only the wrapper that issued a request may fulfil it, and a replaced wrapper
+    /// only until its grace window closes
+    fn only_request_wrapper(&self, request_id: U256) -> Result<(), Error> {
+        let current_wrapper = self.i_vrf_v2_plus_wrapper.get();
+        let request_wrapper = self.vrf_request_wrapper.get(request_id);
+        let msg_sender = self.vm().msg_sender();
+
+        let authorized = if request_wrapper.is_zero() || request_wrapper == current_wrapper {
+            msg_sender == current_wrapper
+        } else {
+            msg_sender == request_wrapper
+                && U256::from(self.vm().block_timestamp())
+                    <= self.vrf_wrapper_retired_until.get(request_wrapper)
+        };
+        if !authorized {
+            return Err(Error::OnlyVRFWrapperCanFulfill(OnlyVRFWrapperCanFulfill {
+                have: msg_sender,
+                want: current_wrapper,
+            }));
+        }
+
+        Ok(())
+    }
+
+    fn round_outcome(&self, round_id: U256) -> RoundOutcome {
+        RoundOutcome::from(self.round_outcomes.get(round_id).to::<u8>())
+    }
+
+    fn set_round_outcome(&mut self, round_id: U256, outcome: RoundOutcome) {
+        self.round_outcomes
+            .insert(round_id, U8::from(outcome as u8));
+    }
+
+    fn round_state(&self, round_id: U256) -> RoundState {
+        RoundState::from(self.round_states.get(round_id).to::<u8>())
+    }
+
+    fn set_round_state(&mut self, round_id: U256, new_state: RoundState) {
+        let previous_state = self.round_state(round_id);
+        self.round_states
+            .insert(round_id, U8::from(new_state as u8));
+
+        log(
+            self.vm(),
+            RoundStateChanged {
+                lotteryId: lottery_of(round_id),
+                roundId: round_id,
+                previousState: previous_state as u8,
+                newState: new_state as u8,
+            },
+        );
+    }
+
+    fn require_round_state(&self, round_id: U256, allowed: &[RoundState]) -> Result<(), Error> {
+        let state = self.round_state(round_id);
+        if !allowed.contains(&state) {
+            return Err(Error::InvalidRoundState(InvalidRoundState {
+                roundId: round_id,
+                state: state as u8,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Returns a game's current round id if its state is one of `allowed`
+    fn require_current_state(
+        &self,
+        lottery_id: U256,
+        allowed: &[RoundState],
+    ) -> Result<U256, Error> {
+        let round_id = self.current_round_of(lottery_id)?;
+        self.require_round_state(round_id, allowed)?;
+        Ok(round_id)
+    }
+
+    /// Current round id of a game, failing for ids no game was created with
+    fn current_round_of(&self, lottery_id: U256) -> Result<U256, Error> {
+        let round_id = self.games.get(lottery_id).current_round.get();
+        if round_id.is_zero() {
+            return Err(Error::LotteryNotFound(LotteryNotFound {
+                lotteryId: lottery_id,
+            }));
+        }
+        Ok(round_id)
+    }
+
+    fn default_round(&self) -> U256 {
+        self.games
+            .get(U256::from(DEFAULT_LOTTERY_ID))
+            .current_round
+            .get()
+    }
+
+    /// Creates a game and opens its first round; returns the new lottery id
+    fn open_lottery(&mut self, entry_fee: U256) -> U256 {
+        let lottery_id = self.lottery_count.get() + U256::from(1);
+        self.lottery_count.set(lottery_id);
+
+        let first_round = round_key(lottery_id, U256::from(1));
+        let mut game = self.games.setter(lottery_id);
+        game.entry_fee.set(entry_fee);
+        game.current_round.set(first_round);
+        self.set_round_state(first_round, RoundState::Open);
+
+        log(
+            self.vm(),
+            LotteryCreated {
+                lotteryId: lottery_id,
+                entryFee: entry_fee,
+                firstRoundId: first_round,
+            },
+        );
+
+        lottery_id
+    }
+
+    /// Sets a game's pot, keeping `current_prize_pool` as the sum over all games
+    fn set_prize_pool(&mut self, lottery_id: U256, amount: U256) {
+        let old_amount = self.games.get(lottery_id).prize_pool.get();
+        self.games.setter(lottery_id).prize_pool.set(amount);
+        let total = self.current_prize_pool.get();
+        self.current_prize_pool.set(total - old_amount + amount);
+    }
+
+    /// Appends a ticket to the round's `players` and inserts its leaf into the round's
+    /// Merkle tree
+    fn record_ticket(&mut self, round_id: U256, player: Address) {
+        let ticket_index = U256::from(self.round_players_count(round_id));
+        self.players.setter(round_id).push(player);
+
+        let mut size = ticket_index + U256::from(1);
+        let mut node = participant_leaf(ticket_index, player);
+        for level in 0..MERKLE_TREE_DEPTH {
+            if size.bit(0) {
+                self.merkle_branch
+                    .setter(round_id)
+                    .insert(U256::from(level), node);
+                return;
+            }
+            node = hash_pair(
+                self.merkle_branch.getter(round_id).get(U256::from(level)),
+                node,
+            );
+            size >>= 1;
+        }
+    }
+
+    /// Root of a round's incremental Merkle tree
+    fn participants_root(&self, round_id: U256) -> B256 {
+        let mut size = U256::from(self.round_players_count(round_id));
+        let mut node = B256::ZERO;
+        let mut zero_subtree = B256::ZERO;
+        for level in 0..MERKLE_TREE_DEPTH {
+            node = if size.bit(0) {
+                hash_pair(
+                    self.merkle_branch.getter(round_id).get(U256::from(level)),
+                    node,
+                )
+            } else {
+                hash_pair(node, zero_subtree)
+            };
+            zero_subtree = hash_pair(zero_subtree, zero_subtree);
+            size >>= 1;
+        }
+        node
+    }
+
+    /// Tickets sold in a round
+    fn round_players_count(&self, round_id: U256) -> usize {
+        self.players.getter(round_id).len()
+    }
+}
+
+/// Round ids are `(lottery_id - 1) << 128 | round number`, so rounds of every game
+/// share the per-round mappings and the default game keeps ids 1, 2, 3, ...
+fn round_key(lottery_id: U256, round_number: U256) -> U256 {
+    ((lottery_id - U256::from(1)) << 128) | round_number
+}
+
+/// Game a round id belongs to
+fn lottery_of(round_id: U256) -> U256 {
+    (round_id >> 128) + U256::from(1)
+}
+
+/// Winner selection: index of the winning ticket within a round of `tickets` tickets
+fn select_ticket(random_word: U256, tickets: usize) -> U256 {
+    random_word % U256::from(tickets)
+}
+
+/// Participants Merkle tree leaf for a ticket
+fn participant_leaf(ticket_index: U256, player: Address) -> B256 {
+    let mut input = [0u8; 52];
+    input[..32].copy_from_slice(&ticket_index.to_be_bytes::<32>());
+    input[32..].copy_from_slice(player.as_slice());
+    keccak256(input)
+}
+
+fn hash_pair(left: B256, right: B256) -> B256 {
+    let mut input = [0u8; 64];
+    input[..32].copy_from_slice(left.as_slice());
+    input[32..].copy_from_slice(right.as_slice());
+    keccak256(input)
+}
+
+/// Allowlist leaf in OpenZeppelin `StandardMerkleTree` format for `(address, uint256)`
+fn allowlist_leaf(player: Address, max_tickets: U256) -> B256 {
+    let mut input = [0u8; 64];
+    input[12..32].copy_from_slice(player.as_slice());
+    input[32..].copy_from_slice(&max_tickets.to_be_bytes::<32>());
+    keccak256(keccak256(input))
+}
+
+/// Verifies a Merkle proof built with sorted-pair hashing
+fn verify_merkle_proof(proof: &[B256], root: B256, leaf: B256) -> bool {
+    let computed = proof.iter().fold(leaf, |node, &sibling| {
+        if node <= sibling {
+            hash_pair(node, sibling)
+        } else {
+            hash_pair(sibling, node)
+        }
+    });
+    computed == root
+}
+
+/// Timelock operation switching to `new_wrapper`
+fn vrf_wrapper_operation_id(new_wrapper: Address) -> B256 {
+    Timelock::operation_id(
+        function_selector!("executeVrfWrapper", Address),
+        &(new_wrapper,).abi_encode_params(),
+    )
+}
+
+/// Derives an independent random word from a VRF word and a nonce
+fn expand_random_word(random_word: U256, nonce: u64) -> U256 {
+    let mut input = [0u8; 40];
+    input[..32].copy_from_slice(&random_word.to_be_bytes::<32>());
+    input[32..].copy_from_slice(&nonce.to_be_bytes());
+    U256::from_be_bytes(keccak256(input).0)
+}
+
+fn get_extra_args_for_native_payment() -> Bytes {
+    let mut extra_args_vec = Vec::new();
+    extra_args_vec.extend_from_slice(&[0x92, 0xfd, 0x13, 0x38]);
+    extra_args_vec.extend_from_slice(&[0x00; 28]);
+    extra_args_vec.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+    extra_args_vec.extend_from_slice(&[0x00; 28]);
+    Bytes::from(extra_args_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use motsu::prelude::{Balance, Contract, Funding};
+    use openzeppelin_stylus::proxy::erc1967::utils::Erc1967Utils;
+    use test_contracts::{calldata, MockErc721, MockVrfWrapper, NextImplementation, Receiver};
+
+    use super::*;
+
+    const WRAPPER: Address = Address::repeat_byte(0x33);
+    const ENTRY_FEE: U256 = U256::from_limbs([1_000, 0, 0, 0]);
+    const GAME: U256 = U256::from_limbs([DEFAULT_LOTTERY_ID, 0, 0, 0]);
+
+    /// Deploys the lottery on `wrapper`, owned by `alice`, with `receiver` pointed at it
+    fn deploy(
+        contract: &Contract<Lottery>,
+        wrapper: &Contract<MockVrfWrapper>,
+        receiver: &Contract<Receiver>,
+        alice: Address,
+    ) {
+        contract
+            .sender(alice)
+            .constructor(wrapper.address(), ENTRY_FEE, alice)
+            .unwrap();
+        receiver.sender(alice).set_target(contract.address());
+    }
+
+    /// Queues a timelocked call to run straight away. motsu's block timestamp never
+    /// moves, so a zero delay stands in for waiting the timelock out.
+    fn queue_now(contract: &Contract<Lottery>, admin: Address, selector: [u8; 4], params: &[u8]) {
+        contract.sender(admin).timelock.set_min_delay(U256::ZERO);
+        let now = U256::from(contract.sender(admin).vm().block_timestamp());
+        contract
+            .sender(admin)
+            .queue_operation(Timelock::operation_id(selector, params), now)
+            .unwrap();
+    }
+
+    /// Has `receiver` buy one ticket of the default game; `funder` pays for it
+    fn enter_receiver(receiver: &Contract<Receiver>, funder: Address) {
+        funder.fund(ENTRY_FEE);
+        receiver
+            .sender_and_value(funder, ENTRY_FEE)
+            .forward(calldata(function_selector!("enterLottery"), &[]))
+            .unwrap();
+    }
+
+    /// Starts the draw of the default game's current round and delivers `random_word`.
+    /// Returns the round.
+    fn draw(
+        contract: &Contract<Lottery>,
+        wrapper: &Contract<MockVrfWrapper>,
+        operator: Address,
+        random_word: U256,
+    ) -> U256 {
+        let round = contract.sender(operator).get_current_round();
+        let request_id = contract.sender(operator).start_game_draw(GAME).unwrap();
+        contract
+            .sender(wrapper.address())
+            .raw_fulfill_random_words(request_id, vec![random_word])
+            .unwrap();
+        round
+    }
+
+    /// Has `receiver` make `call` and make it again from inside the payment it
+    /// triggers, then checks the reentrancy guard refused the second one
+    fn assert_reentry_refused(receiver: &Contract<Receiver>, caller: Address, call: AbiBytes) {
+        receiver.sender(caller).set_reentry(call.clone());
+        receiver.sender(caller).forward(call).unwrap();
+        assert_eq!(
+            receiver.sender(caller).reentry_error().to_vec(),
+            reentrant_call()
+        );
+    }
+
+    fn reentrant_call() -> Vec<u8> {
+        Error::ReentrantCall(reentrancy_guard::ReentrancyGuardReentrantCall {}).into()
+    }
+
+    storage_layout::append_only_test!(
+        Lottery,
+        [
+            AccessControl,
+            Nonces,
+            Ownable2Step,
+            Pausable,
+            ReentrancyGuard,
+            Timelock,
+            UUPSUpgradeable
+        ]
+    );
+
+    test_contracts::owner_and_upgrade_tests!(Lottery, (ENTRY_FEE));
+
+    /// `proxy` is never constructed, so it stands for the proxy's storage the way a
+    /// delegate call from `UpgradeableProxy` would see it
+    #[motsu::test]
+    fn state_survives_a_timelocked_upgrade(
+        proxy: Contract<Lottery>,
+        implementation: Contract<Lottery>,
+        next: Contract<NextImplementation>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        implementation
+            .sender(alice)
+            .constructor(wrapper.address(), ENTRY_FEE, alice)
+            .unwrap();
+        // What the proxy's constructor does before it delegates `initialize`
+        Erc1967Utils::upgrade_to_and_call(
+            &mut *proxy.sender(alice),
+            implementation.address(),
+            &AbiBytes::from(Vec::new()),
+        )
+        .unwrap();
+        proxy
+            .sender(alice)
+            .initialize(wrapper.address(), ENTRY_FEE, alice)
+            .unwrap();
+        receiver.sender(alice).set_target(proxy.address());
+        enter_receiver(&receiver, alice);
+        let round = proxy.sender(alice).get_current_round();
+
+        let params = (next.address(), Bytes::new()).abi_encode_params();
+        queue_now(
+            &proxy,
+            alice,
+            function_selector!("upgradeToAndCall", Address, AbiBytes),
+            &params,
+        );
+        proxy.sender(alice).upgrade_to(next.address()).unwrap();
+
+        // Points the host at the proxy's storage for the slot read
+        let _ = &*proxy.sender(alice);
+        assert_eq!(Erc1967Utils::get_implementation(), next.address());
+        assert_eq!(proxy.sender(alice).owner(), alice);
+        assert_eq!(proxy.sender(alice).get_entry_fee(), ENTRY_FEE);
+        assert_eq!(proxy.sender(alice).get_current_round(), round);
+        assert_eq!(
+            proxy
+                .sender(alice)
+                .get_player_tickets(round, receiver.address()),
+            U256::from(1)
+        );
+        assert_eq!(proxy.sender(alice).get_prize_pool(), ENTRY_FEE);
+        assert_eq!(proxy.balance(), ENTRY_FEE);
+    }
+
+    /// An initialized lottery with an empty ERC-1967 slot, like a factory clone
+    #[motsu::test]
+    fn upgrade_reverts_on_a_clone(
+        clone: Contract<Lottery>,
+        next: Contract<NextImplementation>,
+        alice: Address,
+    ) {
+        clone
+            .sender(alice)
+            .initialize(WRAPPER, ENTRY_FEE, alice)
+            .unwrap();
+        let params = (next.address(), Bytes::new()).abi_encode_params();
+        queue_now(
+            &clone,
+            alice,
+            function_selector!("upgradeToAndCall", Address, AbiBytes),
+            &params,
+        );
+
+        let err = clone.sender(alice).upgrade_to(next.address()).unwrap_err();
+        let expected: Vec<u8> = uups_upgradeable::Error::UnauthorizedCallContext(
+            uups_upgradeable::UUPSUnauthorizedCallContext {},
+        )
+        .into();
+        assert_eq!(err, expected);
+    }
+
+    #[motsu::test]
+    fn subscription_price_overflow_is_rejected(contract: Contract<Lottery>, alice: Address) {
+        contract
+            .sender(alice)
+            .constructor(WRAPPER, ENTRY_FEE, alice)
+            .unwrap();
+
+        let err = contract
+            .sender(alice)
+            .subscribe(U256::MAX, U256::from(1))
+            .unwrap_err();
+        assert!(matches!(err, Error::SubscriptionPriceOverflow(_)));
+
+        let tickets = U256::from(MAX_SUBSCRIPTION_TICKETS + 1);
+        let err = contract
+            .sender(alice)
+            .subscribe(U256::from(1), tickets)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidTicketCount(_)));
+        assert_eq!(
+            contract.sender(alice).total_subscription_balance.get(),
+            U256::ZERO
+        );
+    }
+
+    #[motsu::test]
+    fn subscription_balance_never_goes_negative(contract: Contract<Lottery>, alice: Address) {
+        contract
+            .sender(alice)
+            .constructor(WRAPPER, ENTRY_FEE, alice)
+            .unwrap();
+        let (rounds, tickets) = (U256::from(3), U256::from(2));
+        let price = ENTRY_FEE * tickets;
+        alice.fund(price * rounds);
+
+        contract
+            .sender_and_value(alice, price * rounds)
+            .subscribe(rounds, tickets)
+            .unwrap();
+        // The open round took the first round's tickets straight away
+        assert_eq!(
+            contract.sender(alice).total_subscription_balance.get(),
+            price * U256::from(2)
+        );
+
+        contract
+            .sender(alice)
+            .total_subscription_balance
+            .set(U256::from(1));
+        let err = contract.sender(alice).unsubscribe().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InsufficientSubscriptionBalance(InsufficientSubscriptionBalance { balance, required })
+                if balance == U256::from(1) && required == price * U256::from(2)
+        ));
+        assert_eq!(
+            contract.sender(alice).total_subscription_balance.get(),
+            U256::from(1)
+        );
+    }
+
+    /// Queues and makes a timelocked config call that takes one `uint256`
+    fn configure(
+        contract: &Contract<Lottery>,
+        admin: Address,
+        selector: [u8; 4],
+        value: U256,
+        set: impl FnOnce(&mut Lottery, U256) -> Result<(), Error>,
+    ) {
+        queue_now(contract, admin, selector, &(value,).abi_encode_params());
+        set(&mut contract.sender(admin), value).unwrap();
+    }
+
+    #[motsu::test]
+    fn settle_round_pays_the_winner_and_keeps_the_rollover_share(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        first: Contract<Receiver>,
+        second: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &first, alice);
+        second.sender(alice).set_target(contract.address());
+        configure(
+            &contract,
+            alice,
+            function_selector!("setJackpotRolloverBps", U256),
+            U256::from(1_000),
+            Lottery::set_jackpot_rollover_bps,
+        );
+        enter_receiver(&first, alice);
+        enter_receiver(&second, alice);
+
+        // 7 % 2 picks the second ticket
+        let round = draw(&contract, &wrapper, alice, U256::from(7));
+        contract.sender(alice).settle_round(round).unwrap();
+
+        let rollover = ENTRY_FEE * U256::from(2) / U256::from(10);
+        assert_eq!(
+            contract.sender(alice).get_round_outcome(round),
+            RoundOutcome::Winner as u8
+        );
+        assert_eq!(
+            contract.sender(alice).get_round_winner(round),
+            second.address()
+        );
+        assert_eq!(second.balance(), ENTRY_FEE * U256::from(2) - rollover);
+        assert_eq!(first.balance(), U256::ZERO);
+        assert_eq!(contract.sender(alice).get_jackpot(), rollover);
+        assert_eq!(contract.sender(alice).get_prize_pool(), rollover);
+        assert_eq!(
+            contract.sender(alice).get_current_round(),
+            round + U256::from(1)
+        );
+    }
+
+    #[motsu::test]
+    fn settle_round_rolls_the_pot_over_below_the_minimum_players(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        configure(
+            &contract,
+            alice,
+            function_selector!("setMinPlayersForPayout", U256),
+            U256::from(2),
+            Lottery::set_min_players_for_payout,
+        );
+        enter_receiver(&receiver, alice);
+
+        let round = draw(&contract, &wrapper, alice, U256::from(7));
+        contract.sender(alice).settle_round(round).unwrap();
+
+        assert_eq!(
+            contract.sender(alice).get_round_outcome(round),
+            RoundOutcome::NoWinner as u8
+        );
+        assert_eq!(
+            contract.sender(alice).get_round_winner(round),
+            Address::ZERO
+        );
+        assert_eq!(receiver.balance(), U256::ZERO);
+        assert_eq!(contract.sender(alice).get_jackpot(), ENTRY_FEE);
+        assert_eq!(contract.sender(alice).get_prize_pool(), ENTRY_FEE);
+    }
+
+    #[motsu::test]
+    fn raffle_round_pays_the_treasury_and_carries_sponsorships(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        nft: Contract<MockErc721>,
+        treasury: Contract<Receiver>,
+        alice: Address,
+        bob: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        queue_now(
+            &contract,
+            alice,
+            function_selector!("setTreasury", Address),
+            &(treasury.address(),).abi_encode_params(),
+        );
+        contract
+            .sender(alice)
+            .set_treasury(treasury.address())
+            .unwrap();
+        let token_id = U256::from(42);
+        contract
+            .sender(nft.address())
+            .on_erc721_received(alice, alice, token_id, AbiBytes::from(Vec::new()))
+            .unwrap();
+        let round = contract.sender(alice).get_current_round();
+        bob.fund(ENTRY_FEE);
+        contract
+            .sender_and_value(bob, ENTRY_FEE)
+            .sponsor_round(round)
+            .unwrap();
+        enter_receiver(&receiver, alice);
+
+        draw(&contract, &wrapper, alice, U256::from(7));
+        contract.sender(alice).settle_round(round).unwrap();
+
+        assert_eq!(
+            contract.sender(alice).get_round_outcome(round),
+            RoundOutcome::Raffle as u8
+        );
+        assert_eq!(treasury.balance(), ENTRY_FEE);
+        assert_eq!(receiver.balance(), U256::ZERO);
+        assert_eq!(contract.sender(alice).get_prize_pool(), ENTRY_FEE);
+        assert_eq!(
+            contract.sender(alice).get_nft_prize(U256::ZERO).4,
+            receiver.address()
+        );
+    }
+
+    #[motsu::test]
+    fn cancel_round_refunds_entries_and_keeps_sponsorships(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        first: Contract<Receiver>,
+        second: Contract<Receiver>,
+        alice: Address,
+        bob: Address,
+    ) {
+        deploy(&contract, &wrapper, &first, alice);
+        second.sender(alice).set_target(contract.address());
+        let round = contract.sender(alice).get_current_round();
+        bob.fund(ENTRY_FEE);
+        contract
+            .sender_and_value(bob, ENTRY_FEE)
+            .sponsor_round(round)
+            .unwrap();
+        enter_receiver(&first, alice);
+        enter_receiver(&second, alice);
+
+        contract.sender(alice).cancel_round(GAME).unwrap();
+
+        assert_eq!(contract.sender(alice).get_prize_pool(), ENTRY_FEE);
+        assert_eq!(contract.sender(alice).withdrawable_surplus(), U256::ZERO);
+        let claim = calldata(
+            function_selector!("claimRefund", U256),
+            &(round,).abi_encode_params(),
+        );
+        first.sender(alice).forward(claim.clone()).unwrap();
+        second.sender(alice).forward(claim).unwrap();
+        assert_eq!(first.balance(), ENTRY_FEE);
+        assert_eq!(second.balance(), ENTRY_FEE);
+        assert_eq!(contract.balance(), ENTRY_FEE);
+
+        let err = contract.sender(bob).claim_refund(round).unwrap_err();
+        assert!(matches!(err, Error::NoRefundOwed(_)));
+        let err = contract
+            .sender(bob)
+            .claim_refund(round + U256::from(1))
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidRoundState(_)));
+    }
+
+    #[motsu::test]
+    fn sponsor_round_funds_the_open_round_or_waits_for_a_later_one(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+        bob: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        let round = contract.sender(alice).get_current_round();
+        let next_round = round + U256::from(1);
+        bob.fund(ENTRY_FEE * U256::from(3));
+
+        contract
+            .sender_and_value(bob, ENTRY_FEE)
+            .sponsor_round(round)
+            .unwrap();
+        contract
+            .sender_and_value(bob, ENTRY_FEE * U256::from(2))
+            .sponsor_round(next_round)
+            .unwrap();
+
+        assert_eq!(contract.sender(alice).get_prize_pool(), ENTRY_FEE);
+        assert_eq!(
+            contract.sender(alice).get_round_sponsorship(next_round),
+            ENTRY_FEE * U256::from(2)
+        );
+        assert_eq!(contract.sender(alice).withdrawable_surplus(), U256::ZERO);
+
+        // Nobody entered, so the round's own sponsorship stays in the jackpot
+        contract.sender(alice).cancel_round(GAME).unwrap();
+        assert_eq!(
+            contract.sender(alice).get_prize_pool(),
+            ENTRY_FEE * U256::from(3)
+        );
+
+        let err = contract.sender(bob).sponsor_round(round).unwrap_err();
+        assert!(matches!(err, Error::InvalidSponsorRound(_)));
+    }
+
+    #[motsu::test]
+    fn withdrawable_surplus_leaves_out_the_pot_and_the_vrf_reserve(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        let funding = ENTRY_FEE * U256::from(5);
+        alice.fund(funding);
+        contract
+            .sender_and_value(alice, funding)
+            .fund_contract()
+            .unwrap();
+        enter_receiver(&receiver, alice);
+        assert_eq!(contract.sender(alice).withdrawable_surplus(), funding);
+
+        configure(
+            &contract,
+            alice,
+            function_selector!("setVrfBudgetReserve", U256),
+            ENTRY_FEE,
+            Lottery::set_vrf_budget_reserve,
+        );
+        let surplus = funding - ENTRY_FEE;
+        assert_eq!(contract.sender(alice).withdrawable_surplus(), surplus);
+
+        let amount = surplus + U256::from(1);
+        queue_now(
+            &contract,
+            alice,
+            function_selector!("withdrawNative", U256),
+            &(amount,).abi_encode_params(),
+        );
+        let err = contract.sender(alice).withdraw_native(amount).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InsufficientSurplus(InsufficientSurplus { requested, available })
+                if requested == amount && available == surplus
+        ));
+    }
+
+    #[motsu::test]
+    fn enter_game_with_referrer_credits_the_referrer(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        referrer: Contract<Receiver>,
+        alice: Address,
+        bob: Address,
+        carol: Address,
+    ) {
+        deploy(&contract, &wrapper, &referrer, alice);
+        configure(
+            &contract,
+            alice,
+            function_selector!("setReferralBps", U256),
+            U256::from(1_000),
+            Lottery::set_referral_bps,
+        );
+        let reward = ENTRY_FEE / U256::from(10);
+        bob.fund(ENTRY_FEE * U256::from(2));
+        carol.fund(ENTRY_FEE);
+
+        contract
+            .sender_and_value(bob, ENTRY_FEE)
+            .enter_game_with_referrer(GAME, referrer.address())
+            .unwrap();
+        assert_eq!(
+            contract
+                .sender(alice)
+                .get_referral_balance(referrer.address()),
+            reward
+        );
+        assert_eq!(contract.sender(alice).get_prize_pool(), ENTRY_FEE - reward);
+        assert_eq!(contract.sender(alice).withdrawable_surplus(), U256::ZERO);
+
+        let err = contract
+            .sender_and_value(bob, ENTRY_FEE)
+            .enter_game_with_referrer(GAME, carol)
+            .unwrap_err();
+        assert!(matches!(err, Error::ReferrerAlreadySet(_)));
+        let err = contract
+            .sender_and_value(carol, ENTRY_FEE)
+            .enter_game_with_referrer(GAME, carol)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidReferrer(_)));
+
+        referrer
+            .sender(alice)
+            .forward(calldata(function_selector!("claimReferralRewards"), &[]))
+            .unwrap();
+        assert_eq!(referrer.balance(), reward);
+        assert_eq!(
+            contract
+                .sender(alice)
+                .get_referral_stats(referrer.address()),
+            (U256::from(1), reward)
+        );
+        assert_eq!(
+            contract
+                .sender(alice)
+                .get_referral_balance(referrer.address()),
+            U256::ZERO
+        );
+    }
+
+    #[motsu::test]
+    fn settle_round_refuses_reentry_from_the_winner(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        enter_receiver(&receiver, alice);
+        let round = draw(&contract, &wrapper, alice, U256::from(7));
+
+        let settle = calldata(
+            function_selector!("settleRound", U256),
+            &(round,).abi_encode_params(),
+        );
+        assert_reentry_refused(&receiver, alice, settle);
+
+        assert_eq!(
+            contract.sender(alice).get_round_winner(round),
+            receiver.address()
+        );
+        assert_eq!(receiver.balance(), ENTRY_FEE);
+    }
+
+    #[motsu::test]
+    fn claim_winnings_refuses_reentry(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        enter_receiver(&receiver, alice);
+        let round = draw(&contract, &wrapper, alice, U256::from(7));
+        receiver.sender(alice).set_rejects_payments(true);
+        contract.sender(alice).settle_round(round).unwrap();
+        receiver.sender(alice).set_rejects_payments(false);
+
+        assert_reentry_refused(
+            &receiver,
+            alice,
+            calldata(function_selector!("claimWinnings"), &[]),
+        );
+
+        assert_eq!(receiver.balance(), ENTRY_FEE);
+        assert_eq!(
+            contract
+                .sender(alice)
+                .get_pending_winnings(receiver.address()),
+            U256::ZERO
+        );
+    }
+
+    #[motsu::test]
+    fn claim_refund_refuses_reentry(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        enter_receiver(&receiver, alice);
+        let round = contract.sender(alice).get_current_round();
+        contract.sender(alice).cancel_round(GAME).unwrap();
+
+        let claim = calldata(
+            function_selector!("claimRefund", U256),
+            &(round,).abi_encode_params(),
+        );
+        assert_reentry_refused(&receiver, alice, claim);
+
+        assert_eq!(receiver.balance(), ENTRY_FEE);
+    }
+
+    #[motsu::test]
+    fn claim_referral_rewards_refuses_reentry(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+        bob: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        let referral_bps = U256::from(1_000);
+        queue_now(
+            &contract,
+            alice,
+            function_selector!("setReferralBps", U256),
+            &(referral_bps,).abi_encode_params(),
+        );
+        contract
+            .sender(alice)
+            .set_referral_bps(referral_bps)
+            .unwrap();
+        bob.fund(ENTRY_FEE);
+        contract
+            .sender_and_value(bob, ENTRY_FEE)
+            .enter_game_with_referrer(GAME, receiver.address())
+            .unwrap();
+
+        assert_reentry_refused(
+            &receiver,
+            alice,
+            calldata(function_selector!("claimReferralRewards"), &[]),
+        );
+
+        assert_eq!(receiver.balance(), ENTRY_FEE / U256::from(10));
+    }
+
+    #[motsu::test]
+    fn withdraw_prepaid_refuses_reentry(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        alice.fund(ENTRY_FEE);
+        receiver
+            .sender_and_value(alice, ENTRY_FEE)
+            .forward(calldata(
+                function_selector!("depositPrepaid", Address),
+                &(receiver.address(),).abi_encode_params(),
+            ))
+            .unwrap();
+
+        let withdraw = calldata(
+            function_selector!("withdrawPrepaid", U256),
+            &(ENTRY_FEE,).abi_encode_params(),
+        );
+        assert_reentry_refused(&receiver, alice, withdraw);
+
+        assert_eq!(receiver.balance(), ENTRY_FEE);
+    }
+
+    #[motsu::test]
+    fn unsubscribe_refuses_reentry(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        let rounds = U256::from(3);
+        alice.fund(ENTRY_FEE * rounds);
+        receiver
+            .sender_and_value(alice, ENTRY_FEE * rounds)
+            .forward(calldata(
+                function_selector!("subscribe", U256, U256),
+                &(rounds, U256::from(1)).abi_encode_params(),
+            ))
+            .unwrap();
+
+        assert_reentry_refused(
+            &receiver,
+            alice,
+            calldata(function_selector!("unsubscribe"), &[]),
+        );
+
+        // The open round already took the first of the three
+        assert_eq!(receiver.balance(), ENTRY_FEE * U256::from(2));
+    }
+
+    #[motsu::test]
+    fn claim_syndicate_share_refuses_reentry(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        let syndicate_id = contract.sender(alice).create_syndicate(GAME).unwrap();
+        alice.fund(ENTRY_FEE);
+        receiver
+            .sender_and_value(alice, ENTRY_FEE)
+            .forward(calldata(
+                function_selector!("contributeToSyndicate", U256),
+                &(syndicate_id,).abi_encode_params(),
+            ))
+            .unwrap();
+        let round = draw(&contract, &wrapper, alice, U256::from(7));
+        contract.sender(alice).settle_round(round).unwrap();
+
+        let claim = calldata(
+            function_selector!("claimSyndicateShare", U256),
+            &(syndicate_id,).abi_encode_params(),
+        );
+        assert_reentry_refused(&receiver, alice, claim);
+
+        assert_eq!(receiver.balance(), ENTRY_FEE);
+    }
+
+    #[motsu::test]
+    fn claim_nft_prize_refuses_reentry_from_the_receiver_hook(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        nft: Contract<MockErc721>,
+        treasury: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        let params = (treasury.address(),).abi_encode_params();
+        queue_now(
+            &contract,
+            alice,
+            function_selector!("setTreasury", Address),
+            &params,
+        );
+        contract
+            .sender(alice)
+            .set_treasury(treasury.address())
+            .unwrap();
+        let token_id = U256::from(42);
+        contract
+            .sender(nft.address())
+            .on_erc721_received(alice, alice, token_id, AbiBytes::from(Vec::new()))
+            .unwrap();
+        enter_receiver(&receiver, alice);
+        let round = draw(&contract, &wrapper, alice, U256::from(7));
+        contract.sender(alice).settle_round(round).unwrap();
+
+        let claim = calldata(
+            function_selector!("claimNftPrize", U256),
+            &(U256::ZERO,).abi_encode_params(),
+        );
+        assert_reentry_refused(&receiver, alice, claim);
+
+        assert_eq!(nft.sender(alice).owner_of(token_id), receiver.address());
+        assert_eq!(treasury.balance(), ENTRY_FEE);
+    }
+
+    #[motsu::test]
+    fn withdraw_native_refuses_reentry(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        contract
+            .sender(alice)
+            .grant_role(TREASURER_ROLE.into(), receiver.address())
+            .unwrap();
+        alice.fund(ENTRY_FEE);
+        contract
+            .sender_and_value(alice, ENTRY_FEE)
+            .fund_contract()
+            .unwrap();
+        let params = (ENTRY_FEE,).abi_encode_params();
+        queue_now(
+            &contract,
+            alice,
+            function_selector!("withdrawNative", U256),
+            &params,
+        );
+
+        let withdraw = calldata(function_selector!("withdrawNative", U256), &params);
+        assert_reentry_refused(&receiver, alice, withdraw);
+
+        assert_eq!(receiver.balance(), ENTRY_FEE);
+    }
+
+    #[motsu::test]
+    fn start_draw_refuses_reentry_from_the_vrf_wrapper(
+        contract: Contract<Lottery>,
+        wrapper: Contract<MockVrfWrapper>,
+        receiver: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, &receiver, alice);
+        enter_receiver(&receiver, alice);
+        wrapper
+            .sender(alice)
+            .set_reentry(calldata(function_selector!("startDraw"), &[]));
+
+        contract.sender(alice).start_draw().unwrap();
+
+        assert_eq!(
+            wrapper.sender(alice).reentry_error().to_vec(),
+            reentrant_call()
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! End-to-end check that a proxied `Lottery` keeps its state across an upgrade.
+//!
+//! Needs a node (e.g. the nitro devnode) and these environment variables:
+//! - `RPC_URL`, `PRIV_KEY`: the node and the lottery owner's key
+//! - `LOTTERY_PROXY`: an `UpgradeableProxy` (`upgradeable-proxy`) deployed with the
+//!   `initialize(wrapper, entryFee, owner)` call of a `Lottery` implementation
+//! - `LOTTERY_NEXT_IMPL`: another deployment of the `Lottery` implementation
+//!
+//! Upgrades are timelocked: the `upgradeToAndCall(LOTTERY_NEXT_IMPL, "")` operation must
+//! already be queued with `queueOperation` and past its ETA.
+//!
+//! Run with `cargo test --test upgrade -- --ignored`. The unit test
+//! `state_survives_a_timelocked_upgrade` runs the same upgrade under motsu, against a
+//! stand-in for the next implementation.
+
+use std::{str::FromStr, sync::Arc};
+
+use ethers::{
+    middleware::SignerMiddleware,
+    prelude::abigen,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::Address,
+};
+use eyre::eyre;
+
+abigen!(
+    Lottery,
+    r#"[
+        function enterLottery() external payable
+        function getEntryFee() external view returns (uint256)
+        function getCurrentRound() external view returns (uint256)
+        function getPlayersCount() external view returns (uint256)
+        function getPrizePool() external view returns (uint256)
+        function owner() external view returns (address)
+        function operatorRole() external view returns (bytes32)
+        function hasRole(bytes32 role, address account) external view returns (bool)
+        function upgradeTo(address newImplementation) external
+        function implementation() external view returns (address)
+    ]"#
+);
+
+#[tokio::test]
+#[ignore = "needs a node with a deployed proxy, see the module docs"]
+async fn state_survives_upgrade() -> eyre::Result<()> {
+    let provider = Provider::<Http>::try_from(env("RPC_URL")?)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let wallet = LocalWallet::from_str(&env("PRIV_KEY")?)?.with_chain_id(chain_id);
+    let owner = wallet.address();
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+
+    let proxy: Address = env("LOTTERY_PROXY")?.parse()?;
+    let next_implementation: Address = env("LOTTERY_NEXT_IMPL")?.parse()?;
+    let lottery = Lottery::new(proxy, client.clone());
+
+    let entry_fee = lottery.get_entry_fee().call().await?;
+    lottery
+        .enter_lottery()
+        .value(entry_fee)
+        .send()
+        .await?
+        .await?;
+
+    let round = lottery.get_current_round().call().await?;
+    let players = lottery.get_players_count().call().await?;
+    let prize_pool = lottery.get_prize_pool().call().await?;
+    let operator_role = lottery.operator_role().call().await?;
+    let balance = client.get_balance(proxy, None).await?;
+    assert!(!players.is_zero());
+
+    lottery
+        .upgrade_to(next_implementation)
+        .send()
+        .await?
+        .await?;
+
+    assert_eq!(lottery.implementation().call().await?, next_implementation);
+    assert_eq!(lottery.owner().call().await?, owner);
+    assert!(lottery.has_role(operator_role, owner).call().await?);
+    assert_eq!(lottery.get_entry_fee().call().await?, entry_fee);
+    assert_eq!(lottery.get_current_round().call().await?, round);
+    assert_eq!(lottery.get_players_count().call().await?, players);
+    assert_eq!(lottery.get_prize_pool().call().await?, prize_pool);
+    assert_eq!(client.get_balance(proxy, None).await?, balance);
+
+    // The upgraded lottery keeps taking entries into the same round
+    lottery
+        .enter_lottery()
+        .value(entry_fee)
+        .send()
+        .await?
+        .await?;
+    assert_eq!(lottery.get_players_count().call().await?, players + 1);
+
+    Ok(())
+}
+
+fn env(name: &str) -> eyre::Result<String> {
+    std::env::var(name).map_err(|_| eyre!("{name} is not set"))
+}
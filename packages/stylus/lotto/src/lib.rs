@@ -0,0 +1,1442 @@
+//!
+//! Pick-your-numbers Lotto Contract in Stylus Rust
+//!
+//! Players pick K distinct numbers from 1..N. Chainlink VRF draws K numbers per round
+//! and payouts follow match-count tiers. Whatever a round doesn't pay out, including
+//! the top tier when nobody hits it, rolls over into the next round's jackpot.
+//!
+//! The owner can pause ticket sales, draws and payouts separately, and every call
+//! that sends value out holds a reentrancy guard.
+//!
+
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    alloy_primitives::{keccak256, Address, Bytes, U16, U256, U32, U8},
+    alloy_sol_types::sol,
+    prelude::*,
+    stylus_core::calls::context::Call,
+    stylus_core::log,
+};
+
+#[allow(deprecated)]
+use stylus_sdk::call::Call as OldCall;
+
+use stylus_common::{
+    pause::PauseScope,
+    reentrancy_guard::{self, ReentrancyGuard},
+};
+
+use openzeppelin_stylus::{
+    access::ownable::{self, Ownable},
+    utils::pausable::{self, IPausable, Pausable},
+};
+
+/// Numbers are stored as bits of a U256, so the highest pickable number is 255
+const MAX_NUMBER_LIMIT: u8 = 255;
+/// Upper bound on K, keeps the draw inside the VRF callback gas limit
+const MAX_PICK_COUNT: u8 = 10;
+const BPS_DENOMINATOR: u64 = 10_000;
+/// How long a draw may wait for its randomness before anyone can cancel it
+const DEFAULT_DRAW_TIMEOUT: u64 = 24 * 60 * 60;
+
+// Define persistent storage
+sol_storage! {
+    #[entrypoint]
+    pub struct Lotto {
+        address i_vrf_v2_plus_wrapper;
+        uint32 callback_gas_limit;
+        uint16 request_confirmations;
+        uint32 num_words;
+        uint256 vrf_budget;
+        uint256 draw_timeout;
+
+        uint256 ticket_price;
+        uint8 pick_count;
+        uint8 max_number;
+        mapping(uint8 => uint256) tier_share_bps;
+
+        Ticket[] tickets;
+        uint256 current_round;
+        bool round_open;
+        uint256 jackpot;
+        uint256 last_settled_round;
+
+        mapping(uint256 => uint256) vrf_requests;
+        mapping(uint256 => uint256) round_revenue;
+        mapping(uint256 => uint256) round_first_ticket;
+        mapping(uint256 => uint256) round_end_ticket;
+        mapping(uint256 => uint256) round_drawn;
+        mapping(uint256 => uint256) round_settle_cursor;
+        mapping(uint256 => mapping(uint8 => uint256)) round_tier_winners;
+        mapping(uint256 => mapping(uint8 => uint256)) round_tier_payout;
+        mapping(uint256 => uint256) round_draw_started_at;
+        mapping(uint256 => bool) round_cancelled;
+        mapping(uint256 => mapping(address => uint256)) round_contributions;
+
+        Ownable ownable;
+        Pausable entries_pausable; // stops ticket sales
+        Pausable draws_pausable; // stops new draws
+        Pausable payouts_pausable; // stops settlement, prizes and refunds
+        ReentrancyGuard reentrancy; // held while value leaves the contract
+    }
+
+    /// A single entry: the picked numbers as a bitmask (bit i set = number i picked)
+    pub struct Ticket {
+        address player;
+        uint256 round;
+        uint256 numbers;
+        bool claimed;
+    }
+}
+
+// VRF V2+ Wrapper interface
+sol_interface! {
+    interface IVRFV2PlusWrapper {
+        function calculateRequestPriceNative(uint32 _callback_gas_limit, uint32 _num_words) external view returns (uint256);
+        function requestRandomWordsInNative(
+            uint32 _callback_gas_limit,
+            uint16 _request_confirmations,
+            uint32 _num_words,
+            bytes calldata extra_args
+        ) external payable returns (uint256 requestId);
+    }
+}
+
+// Events
+sol! {
+    event TicketPurchased(address indexed player, uint256 indexed round, uint256 indexed ticketId, uint8[] numbers);
+    event DrawStarted(uint256 indexed round, uint256 indexed requestId, uint256 ticketsCount);
+    event NumbersDrawn(uint256 indexed round, uint8[] numbers, uint256 randomWord);
+    event RoundSettled(uint256 indexed round, uint256 pot, uint256 jackpotRollover);
+    event PrizeClaimed(uint256 indexed round, uint256 indexed ticketId, address indexed player, uint8 matches, uint256 amount);
+    event TierShareUpdated(uint8 matches, uint256 oldShareBps, uint256 newShareBps);
+    event TicketPriceUpdated(uint256 oldPrice, uint256 newPrice);
+    event DrawCancelled(uint256 indexed round, uint256 refunds);
+    event RefundClaimed(uint256 indexed round, address indexed player, uint256 amount);
+    event DrawTimeoutUpdated(uint256 oldTimeout, uint256 newTimeout);
+    event VRFBudgetFunded(address indexed funder, uint256 amount);
+    event VRFRequestSent(uint256 indexed requestId, uint32 numWords);
+    event VRFRequestFulfilled(uint256 indexed requestId, uint256[] randomWords, uint256 payment);
+    event Received(address indexed sender, uint256 value);
+    event PauseUpdated(uint8 indexed scope, bool paused, address guardian);
+}
+
+// Errors
+sol! {
+    #[derive(Debug)]
+    error OnlyVRFWrapperCanFulfill(address have, address want);
+
+    #[derive(Debug)]
+    error InvalidGameConfig(uint8 pickCount, uint8 maxNumber);
+
+    #[derive(Debug)]
+    error RoundNotOpen();
+
+    #[derive(Debug)]
+    error InsufficientTicketPrice(uint256 sent, uint256 required);
+
+    #[derive(Debug)]
+    error InvalidPickCount(uint256 picked, uint8 required);
+
+    #[derive(Debug)]
+    error InvalidNumber(uint8 number, uint8 maxNumber);
+
+    #[derive(Debug)]
+    error DuplicateNumber(uint8 number);
+
+    #[derive(Debug)]
+    error NoTicketsInRound();
+
+    #[derive(Debug)]
+    error PreviousRoundNotSettled(uint256 round);
+
+    #[derive(Debug)]
+    error RoundNotDrawn(uint256 round);
+
+    #[derive(Debug)]
+    error RoundAlreadySettled(uint256 round);
+
+    #[derive(Debug)]
+    error RoundNotSettled(uint256 round);
+
+    #[derive(Debug)]
+    error InvalidTier(uint8 matches);
+
+    #[derive(Debug)]
+    error TierSharesExceedTotal(uint256 totalBps);
+
+    #[derive(Debug)]
+    error TicketNotFound(uint256 ticketId);
+
+    #[derive(Debug)]
+    error NotTicketOwner(uint256 ticketId, address caller);
+
+    #[derive(Debug)]
+    error PrizeAlreadyClaimed(uint256 ticketId);
+
+    #[derive(Debug)]
+    error NoPrizeForTicket(uint256 ticketId);
+
+    #[derive(Debug)]
+    error TransferFailed();
+
+    #[derive(Debug)]
+    error InsufficientVrfBudget(uint256 price, uint256 budget);
+
+    #[derive(Debug)]
+    error NoDrawInProgress();
+
+    #[derive(Debug)]
+    error DrawNotTimedOut(uint256 round, uint256 cancellableAt);
+
+    #[derive(Debug)]
+    error RoundNotCancelled(uint256 round);
+
+    #[derive(Debug)]
+    error NoRefundOwed(uint256 round, address player);
+
+    #[derive(Debug)]
+    error InvalidPauseScope(uint8 scope);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Error {
+    OnlyVRFWrapperCanFulfill(OnlyVRFWrapperCanFulfill),
+    InvalidGameConfig(InvalidGameConfig),
+    RoundNotOpen(RoundNotOpen),
+    InsufficientTicketPrice(InsufficientTicketPrice),
+    InvalidPickCount(InvalidPickCount),
+    InvalidNumber(InvalidNumber),
+    DuplicateNumber(DuplicateNumber),
+    NoTicketsInRound(NoTicketsInRound),
+    PreviousRoundNotSettled(PreviousRoundNotSettled),
+    RoundNotDrawn(RoundNotDrawn),
+    RoundAlreadySettled(RoundAlreadySettled),
+    RoundNotSettled(RoundNotSettled),
+    InvalidTier(InvalidTier),
+    TierSharesExceedTotal(TierSharesExceedTotal),
+    TicketNotFound(TicketNotFound),
+    NotTicketOwner(NotTicketOwner),
+    PrizeAlreadyClaimed(PrizeAlreadyClaimed),
+    NoPrizeForTicket(NoPrizeForTicket),
+    TransferFailed(TransferFailed),
+    InsufficientVrfBudget(InsufficientVrfBudget),
+    NoDrawInProgress(NoDrawInProgress),
+    DrawNotTimedOut(DrawNotTimedOut),
+    RoundNotCancelled(RoundNotCancelled),
+    NoRefundOwed(NoRefundOwed),
+    InvalidPauseScope(InvalidPauseScope),
+    UnauthorizedAccount(ownable::OwnableUnauthorizedAccount),
+    InvalidOwner(ownable::OwnableInvalidOwner),
+    EnforcedPause(pausable::EnforcedPause),
+    ExpectedPause(pausable::ExpectedPause),
+    ReentrantCall(reentrancy_guard::ReentrancyGuardReentrantCall),
+}
+
+impl From<ownable::Error> for Error {
+    fn from(value: ownable::Error) -> Self {
+        match value {
+            ownable::Error::UnauthorizedAccount(e) => Error::UnauthorizedAccount(e),
+            ownable::Error::InvalidOwner(e) => Error::InvalidOwner(e),
+        }
+    }
+}
+
+impl From<pausable::Error> for Error {
+    fn from(value: pausable::Error) -> Self {
+        match value {
+            pausable::Error::EnforcedPause(e) => Error::EnforcedPause(e),
+            pausable::Error::ExpectedPause(e) => Error::ExpectedPause(e),
+        }
+    }
+}
+
+impl From<reentrancy_guard::Error> for Error {
+    fn from(value: reentrancy_guard::Error) -> Self {
+        match value {
+            reentrancy_guard::Error::ReentrantCall(e) => Error::ReentrantCall(e),
+        }
+    }
+}
+
+#[public]
+impl Lotto {
+    /// Constructor - initializes a K-of-N lotto with no tier payouts configured
+    #[constructor]
+    pub fn constructor(
+        &mut self,
+        vrf_v2_plus_wrapper: Address,
+        ticket_price: U256,
+        pick_count: u8,
+        max_number: u8,
+        owner: Address,
+    ) -> Result<(), Error> {
+        if pick_count == 0 || pick_count > MAX_PICK_COUNT || pick_count > max_number {
+            return Err(Error::InvalidGameConfig(InvalidGameConfig {
+                pickCount: pick_count,
+                maxNumber: max_number,
+            }));
+        }
+
+        self.ownable.constructor(owner)?;
+        self.i_vrf_v2_plus_wrapper.set(vrf_v2_plus_wrapper);
+        self.ticket_price.set(ticket_price);
+        self.pick_count.set(U8::from(pick_count));
+        self.max_number.set(U8::from(max_number));
+
+        self.current_round.set(U256::from(1));
+        self.round_open.set(true);
+
+        self.callback_gas_limit.set(U32::from(200000));
+        self.request_confirmations.set(U16::from(3));
+        self.num_words.set(U32::from(1));
+        self.draw_timeout.set(U256::from(DEFAULT_DRAW_TIMEOUT));
+
+        Ok(())
+    }
+
+    /// Players buy a ticket with exactly K distinct numbers from 1..N
+    #[payable]
+    pub fn enter(&mut self, numbers: Vec<u8>) -> Result<U256, Error> {
+        self.entries_pausable.when_not_paused()?;
+        if !self.round_open.get() {
+            return Err(Error::RoundNotOpen(RoundNotOpen {}));
+        }
+
+        let msg_value = self.vm().msg_value();
+        let required_price = self.ticket_price.get();
+        if msg_value < required_price {
+            return Err(Error::InsufficientTicketPrice(InsufficientTicketPrice {
+                sent: msg_value,
+                required: required_price,
+            }));
+        }
+
+        let pick_count = self.pick_count.get().to::<u8>();
+        let max_number = self.max_number.get().to::<u8>();
+        let mask = numbers_to_mask(&numbers, pick_count, max_number)?;
+
+        let player = self.vm().msg_sender();
+        let round = self.current_round.get();
+        let ticket_id = U256::from(self.tickets.len());
+
+        let mut ticket = self.tickets.grow();
+        ticket.player.set(player);
+        ticket.round.set(round);
+        ticket.numbers.set(mask);
+
+        let revenue = self.round_revenue.get(round);
+        self.round_revenue.insert(round, revenue + msg_value);
+        let contribution = self.round_contributions.getter(round).get(player);
+        self.round_contributions
+            .setter(round)
+            .insert(player, contribution + msg_value);
+
+        log(
+            self.vm(),
+            TicketPurchased {
+                player,
+                round,
+                ticketId: ticket_id,
+                numbers,
+            },
+        );
+
+        Ok(ticket_id)
+    }
+
+    /// Owner closes the current round and requests the winning numbers, paid from the
+    /// VRF budget rather than the pot
+    pub fn start_draw(&mut self) -> Result<U256, Error> {
+        self.ownable.only_owner()?;
+
+        self.nonreentrant(|this| {
+            this.draws_pausable.when_not_paused()?;
+            if !this.round_open.get() {
+                return Err(Error::RoundNotOpen(RoundNotOpen {}));
+            }
+
+            let round = this.current_round.get();
+            let previous_round = round - U256::from(1);
+            if this.last_settled_round.get() < previous_round {
+                return Err(Error::PreviousRoundNotSettled(PreviousRoundNotSettled {
+                    round: previous_round,
+                }));
+            }
+
+            let first_ticket = this.round_first_ticket.get(round);
+            let end_ticket = U256::from(this.tickets.len());
+            if end_ticket == first_ticket {
+                return Err(Error::NoTicketsInRound(NoTicketsInRound {}));
+            }
+
+            this.round_open.set(false);
+            this.round_end_ticket.insert(round, end_ticket);
+            this.round_settle_cursor.insert(round, first_ticket);
+            let now = U256::from(this.vm().block_timestamp());
+            this.round_draw_started_at.insert(round, now);
+
+            let callback_gas_limit = this.callback_gas_limit.get().try_into().unwrap_or(200000);
+            let request_confirmations = this.request_confirmations.get().try_into().unwrap_or(3);
+            let num_words = this.num_words.get().try_into().unwrap_or(1);
+
+            let (request_id, _) = this.request_randomness_pay_in_native(
+                callback_gas_limit,
+                request_confirmations,
+                num_words,
+            )?;
+
+            this.vrf_requests.insert(request_id, round);
+
+            log(
+                this.vm(),
+                DrawStarted {
+                    round,
+                    requestId: request_id,
+                    ticketsCount: end_ticket - first_ticket,
+                },
+            );
+
+            log(
+                this.vm(),
+                VRFRequestSent {
+                    requestId: request_id,
+                    numWords: num_words,
+                },
+            );
+
+            Ok(request_id)
+        })
+    }
+
+    /// Anyone can cancel a draw whose randomness hasn't arrived within the draw timeout.
+    /// Its tickets become refundable through `claim_refund`, the jackpot carries over
+    /// and a late fulfilment is ignored.
+    pub fn cancel_draw(&mut self) -> Result<(), Error> {
+        if self.round_open.get() {
+            return Err(Error::NoDrawInProgress(NoDrawInProgress {}));
+        }
+
+        let round = self.current_round.get();
+        let cancellable_at = self.round_draw_started_at.get(round) + self.draw_timeout.get();
+        if U256::from(self.vm().block_timestamp()) < cancellable_at {
+            return Err(Error::DrawNotTimedOut(DrawNotTimedOut {
+                round,
+                cancellableAt: cancellable_at,
+            }));
+        }
+
+        self.round_cancelled.insert(round, true);
+        self.last_settled_round.set(round);
+        self.open_next_round(round);
+
+        log(
+            self.vm(),
+            DrawCancelled {
+                round,
+                refunds: self.round_revenue.get(round),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Players of a cancelled round pull back what they paid for its tickets
+    pub fn claim_refund(&mut self, round: U256) -> Result<U256, Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            if !this.round_cancelled.get(round) {
+                return Err(Error::RoundNotCancelled(RoundNotCancelled { round }));
+            }
+
+            let player = this.vm().msg_sender();
+            let amount = this.round_contributions.getter(round).get(player);
+            if amount.is_zero() {
+                return Err(Error::NoRefundOwed(NoRefundOwed { round, player }));
+            }
+
+            this.round_contributions
+                .setter(round)
+                .insert(player, U256::ZERO);
+
+            this.vm()
+                .call(&Call::new().value(amount), player, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(
+                this.vm(),
+                RefundClaimed {
+                    round,
+                    player,
+                    amount,
+                },
+            );
+
+            Ok(amount)
+        })
+    }
+
+    /// Internal function to request randomness
+    fn request_randomness_pay_in_native(
+        &mut self,
+        callback_gas_limit: u32,
+        request_confirmations: u16,
+        num_words: u32,
+    ) -> Result<(U256, U256), Error> {
+        let external_vrf_wrapper_address = self.i_vrf_v2_plus_wrapper.get();
+        let external_vrf_wrapper = IVRFV2PlusWrapper::new(external_vrf_wrapper_address);
+
+        // Calculate request price
+        let request_price = external_vrf_wrapper
+            .calculate_request_price_native(&mut *self, callback_gas_limit, num_words)
+            .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+        let budget = self.vrf_budget.get();
+        if request_price > budget {
+            return Err(Error::InsufficientVrfBudget(InsufficientVrfBudget {
+                price: request_price,
+                budget,
+            }));
+        }
+        self.vrf_budget.set(budget - request_price);
+
+        let extra_args = get_extra_args_for_native_payment();
+
+        #[allow(deprecated)]
+        let config = OldCall::new().value(request_price);
+
+        // Request random words
+        let request_id = external_vrf_wrapper
+            .request_random_words_in_native(
+                config,
+                callback_gas_limit,
+                request_confirmations,
+                num_words,
+                extra_args,
+            )
+            .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+        Ok((request_id, request_price))
+    }
+
+    /// Callback called by VRF wrapper
+    pub fn raw_fulfill_random_words(
+        &mut self,
+        request_id: U256,
+        random_words: Vec<U256>,
+    ) -> Result<(), Error> {
+        let vrf_wrapper_addr = self.i_vrf_v2_plus_wrapper.get();
+        let msg_sender = self.vm().msg_sender();
+
+        if msg_sender != vrf_wrapper_addr {
+            return Err(Error::OnlyVRFWrapperCanFulfill(OnlyVRFWrapperCanFulfill {
+                have: msg_sender,
+                want: vrf_wrapper_addr,
+            }));
+        }
+
+        log(
+            self.vm(),
+            VRFRequestFulfilled {
+                requestId: request_id,
+                randomWords: random_words.clone(),
+                payment: U256::ZERO,
+            },
+        );
+
+        self.fulfill_random_words(request_id, random_words)
+    }
+
+    /// Internal function to draw the winning numbers and open the next round
+    fn fulfill_random_words(
+        &mut self,
+        request_id: U256,
+        random_words: Vec<U256>,
+    ) -> Result<(), Error> {
+        if random_words.is_empty() {
+            return Ok(());
+        }
+
+        let round = self.vrf_requests.get(request_id);
+        if round != self.current_round.get() || self.round_open.get() {
+            return Ok(());
+        }
+
+        let random_word = random_words[0];
+        let drawn = self.draw_numbers(random_word);
+        self.round_drawn.insert(round, drawn);
+
+        log(
+            self.vm(),
+            NumbersDrawn {
+                round,
+                numbers: mask_to_numbers(drawn),
+                randomWord: random_word,
+            },
+        );
+
+        self.open_next_round(round);
+
+        Ok(())
+    }
+
+    /// Internal function to open the round after `round`
+    fn open_next_round(&mut self, round: U256) {
+        let next_round = round + U256::from(1);
+        self.current_round.set(next_round);
+        self.round_first_ticket
+            .insert(next_round, U256::from(self.tickets.len()));
+        self.round_open.set(true);
+    }
+
+    /// Internal function to expand a VRF word into K distinct numbers from 1..N
+    fn draw_numbers(&self, random_word: U256) -> U256 {
+        let pick_count = self.pick_count.get().to::<u8>();
+        let max_number = U256::from(self.max_number.get().to::<u8>());
+
+        let mut drawn = U256::ZERO;
+        let mut picked = 0u8;
+        let mut nonce = 0u64;
+        while picked < pick_count {
+            let word = expand_random_word(random_word, nonce);
+            nonce += 1;
+            let number: usize = (word % max_number).to::<usize>() + 1;
+            if !drawn.bit(number) {
+                drawn.set_bit(number, true);
+                picked += 1;
+            }
+        }
+
+        drawn
+    }
+
+    /// Counts winners per tier for up to `max_tickets` tickets of a drawn round.
+    /// Once every ticket is counted the round's payouts are fixed and the rest rolls over.
+    pub fn settle_round(&mut self, round: U256, max_tickets: U256) -> Result<bool, Error> {
+        self.payouts_pausable.when_not_paused()?;
+        let drawn = self.round_drawn.get(round);
+        if drawn.is_zero() {
+            return Err(Error::RoundNotDrawn(RoundNotDrawn { round }));
+        }
+        if round <= self.last_settled_round.get() {
+            return Err(Error::RoundAlreadySettled(RoundAlreadySettled { round }));
+        }
+        let previous_round = round - U256::from(1);
+        if self.last_settled_round.get() < previous_round {
+            return Err(Error::PreviousRoundNotSettled(PreviousRoundNotSettled {
+                round: previous_round,
+            }));
+        }
+
+        let end_ticket = self.round_end_ticket.get(round);
+        let cursor = self.round_settle_cursor.get(round);
+        let batch_end = end_ticket.min(cursor.saturating_add(max_tickets));
+
+        let mut index = cursor;
+        while index < batch_end {
+            let numbers = self
+                .tickets
+                .get(index.to::<usize>())
+                .map(|ticket| ticket.numbers.get())
+                .unwrap_or_default();
+            let matches = U8::from((numbers & drawn).count_ones());
+            let winners = self.round_tier_winners.getter(round).get(matches);
+            self.round_tier_winners
+                .setter(round)
+                .insert(matches, winners + U256::from(1));
+            index += U256::from(1);
+        }
+        self.round_settle_cursor.insert(round, batch_end);
+
+        if batch_end < end_ticket {
+            return Ok(false);
+        }
+
+        self.finalize_round(round);
+        Ok(true)
+    }
+
+    /// Internal function to fix per-winner payouts and carry the remainder forward
+    fn finalize_round(&mut self, round: U256) {
+        let pot = self.round_revenue.get(round) + self.jackpot.get();
+        let pick_count = self.pick_count.get().to::<u8>();
+
+        let mut allocated = U256::ZERO;
+        for matches in 1..=pick_count {
+            let tier = U8::from(matches);
+            let winners = self.round_tier_winners.getter(round).get(tier);
+            if winners.is_zero() {
+                continue;
+            }
+            let tier_pool = pot * self.tier_share_bps.get(tier) / U256::from(BPS_DENOMINATOR);
+            let payout = tier_pool / winners;
+            self.round_tier_payout.setter(round).insert(tier, payout);
+            allocated += payout * winners;
+        }
+
+        let rollover = pot - allocated;
+        self.jackpot.set(rollover);
+        self.last_settled_round.set(round);
+
+        log(
+            self.vm(),
+            RoundSettled {
+                round,
+                pot,
+                jackpotRollover: rollover,
+            },
+        );
+    }
+
+    /// Ticket holders pull the payout for their match tier once the round is settled
+    pub fn claim_prize(&mut self, ticket_id: U256) -> Result<U256, Error> {
+        self.nonreentrant(|this| {
+            this.payouts_pausable.when_not_paused()?;
+            let caller = this.vm().msg_sender();
+            let index: usize = ticket_id.try_into().unwrap_or(usize::MAX);
+
+            let Some(ticket) = this.tickets.get(index) else {
+                return Err(Error::TicketNotFound(TicketNotFound {
+                    ticketId: ticket_id,
+                }));
+            };
+            if ticket.player.get() != caller {
+                return Err(Error::NotTicketOwner(NotTicketOwner {
+                    ticketId: ticket_id,
+                    caller,
+                }));
+            }
+            if ticket.claimed.get() {
+                return Err(Error::PrizeAlreadyClaimed(PrizeAlreadyClaimed {
+                    ticketId: ticket_id,
+                }));
+            }
+
+            let round = ticket.round.get();
+            if round > this.last_settled_round.get() {
+                return Err(Error::RoundNotSettled(RoundNotSettled { round }));
+            }
+
+            let matches = (ticket.numbers.get() & this.round_drawn.get(round)).count_ones() as u8;
+            let payout = this.round_tier_payout.getter(round).get(U8::from(matches));
+            if payout.is_zero() {
+                return Err(Error::NoPrizeForTicket(NoPrizeForTicket {
+                    ticketId: ticket_id,
+                }));
+            }
+
+            if let Some(mut ticket) = this.tickets.setter(index) {
+                ticket.claimed.set(true);
+            }
+
+            this.vm()
+                .call(&Call::new().value(payout), caller, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(
+                this.vm(),
+                PrizeClaimed {
+                    round,
+                    ticketId: ticket_id,
+                    player: caller,
+                    matches,
+                    amount: payout,
+                },
+            );
+
+            Ok(payout)
+        })
+    }
+
+    /// Owner sets the share of the pot (in basis points) paid to a match tier
+    pub fn set_tier_share(&mut self, matches: u8, share_bps: U256) -> Result<(), Error> {
+        self.ownable.only_owner()?;
+
+        let pick_count = self.pick_count.get().to::<u8>();
+        if matches == 0 || matches > pick_count {
+            return Err(Error::InvalidTier(InvalidTier { matches }));
+        }
+
+        let old_share = self.tier_share_bps.get(U8::from(matches));
+        let mut total = share_bps;
+        for tier in 1..=pick_count {
+            if tier != matches {
+                total += self.tier_share_bps.get(U8::from(tier));
+            }
+        }
+        if total > U256::from(BPS_DENOMINATOR) {
+            return Err(Error::TierSharesExceedTotal(TierSharesExceedTotal {
+                totalBps: total,
+            }));
+        }
+
+        self.tier_share_bps.insert(U8::from(matches), share_bps);
+
+        log(
+            self.vm(),
+            TierShareUpdated {
+                matches,
+                oldShareBps: old_share,
+                newShareBps: share_bps,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Owner can update the ticket price
+    pub fn set_ticket_price(&mut self, new_price: U256) -> Result<(), Error> {
+        self.ownable.only_owner()?;
+        let old_price = self.ticket_price.get();
+        self.ticket_price.set(new_price);
+
+        log(
+            self.vm(),
+            TicketPriceUpdated {
+                oldPrice: old_price,
+                newPrice: new_price,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Owner sets how long a draw may wait for randomness before it can be cancelled
+    pub fn set_draw_timeout(&mut self, timeout: U256) -> Result<(), Error> {
+        self.ownable.only_owner()?;
+        let old_timeout = self.draw_timeout.get();
+        self.draw_timeout.set(timeout);
+
+        log(
+            self.vm(),
+            DrawTimeoutUpdated {
+                oldTimeout: old_timeout,
+                newTimeout: timeout,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Owner flips the `PauseScope::Entries` switch, which stops ticket sales, the
+    /// `PauseScope::VrfRequests` one, which stops new draws while a draw in flight still
+    /// gets its numbers, or the `PauseScope::Payouts` one, which stops settlement,
+    /// prizes and refunds
+    pub fn set_paused(&mut self, scope: u8, paused: bool) -> Result<(), Error> {
+        self.ownable.only_owner()?;
+        let pausable = match PauseScope::try_from(scope) {
+            Ok(PauseScope::Entries) => &mut self.entries_pausable,
+            Ok(PauseScope::VrfRequests) => &mut self.draws_pausable,
+            Ok(PauseScope::Payouts) => &mut self.payouts_pausable,
+            Err(_) => return Err(Error::InvalidPauseScope(InvalidPauseScope { scope })),
+        };
+        if paused {
+            pausable.pause()?;
+        } else {
+            pausable.unpause()?;
+        }
+
+        log(
+            self.vm(),
+            PauseUpdated {
+                scope,
+                paused,
+                guardian: self.vm().msg_sender(),
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn is_paused(&self, scope: u8) -> Result<bool, Error> {
+        let pausable = match PauseScope::try_from(scope) {
+            Ok(PauseScope::Entries) => &self.entries_pausable,
+            Ok(PauseScope::VrfRequests) => &self.draws_pausable,
+            Ok(PauseScope::Payouts) => &self.payouts_pausable,
+            Err(_) => return Err(Error::InvalidPauseScope(InvalidPauseScope { scope })),
+        };
+        Ok(pausable.paused())
+    }
+
+    /// View functions
+    pub fn get_ticket_price(&self) -> U256 {
+        self.ticket_price.get()
+    }
+
+    pub fn get_game_config(&self) -> (u8, u8) {
+        (
+            self.pick_count.get().to::<u8>(),
+            self.max_number.get().to::<u8>(),
+        )
+    }
+
+    pub fn get_tier_share(&self, matches: u8) -> U256 {
+        self.tier_share_bps.get(U8::from(matches))
+    }
+
+    pub fn get_current_round(&self) -> U256 {
+        self.current_round.get()
+    }
+
+    pub fn is_round_open(&self) -> bool {
+        self.round_open.get()
+    }
+
+    pub fn get_jackpot(&self) -> U256 {
+        self.jackpot.get()
+    }
+
+    pub fn get_last_settled_round(&self) -> U256 {
+        self.last_settled_round.get()
+    }
+
+    pub fn get_round_revenue(&self, round: U256) -> U256 {
+        self.round_revenue.get(round)
+    }
+
+    pub fn is_round_cancelled(&self, round: U256) -> bool {
+        self.round_cancelled.get(round)
+    }
+
+    pub fn get_refund_owed(&self, round: U256, player: Address) -> U256 {
+        self.round_contributions.getter(round).get(player)
+    }
+
+    pub fn get_draw_timeout(&self) -> U256 {
+        self.draw_timeout.get()
+    }
+
+    pub fn get_vrf_budget(&self) -> U256 {
+        self.vrf_budget.get()
+    }
+
+    pub fn get_drawn_numbers(&self, round: U256) -> Vec<u8> {
+        mask_to_numbers(self.round_drawn.get(round))
+    }
+
+    pub fn get_tier_winners(&self, round: U256, matches: u8) -> U256 {
+        self.round_tier_winners.getter(round).get(U8::from(matches))
+    }
+
+    pub fn get_tier_payout(&self, round: U256, matches: u8) -> U256 {
+        self.round_tier_payout.getter(round).get(U8::from(matches))
+    }
+
+    pub fn get_tickets_count(&self) -> U256 {
+        U256::from(self.tickets.len())
+    }
+
+    /// Returns (player, round, numbers, claimed)
+    pub fn get_ticket(&self, ticket_id: U256) -> (Address, U256, Vec<u8>, bool) {
+        let idx: usize = ticket_id.try_into().unwrap_or(usize::MAX);
+        match self.tickets.get(idx) {
+            Some(ticket) => (
+                ticket.player.get(),
+                ticket.round.get(),
+                mask_to_numbers(ticket.numbers.get()),
+                ticket.claimed.get(),
+            ),
+            None => (Address::ZERO, U256::ZERO, Vec::new(), false),
+        }
+    }
+
+    pub fn owner(&self) -> Address {
+        self.ownable.owner()
+    }
+
+    pub fn i_vrf_v2_plus_wrapper(&self) -> Address {
+        self.i_vrf_v2_plus_wrapper.get()
+    }
+
+    pub fn callback_gas_limit(&self) -> U32 {
+        self.callback_gas_limit.get()
+    }
+
+    pub fn request_confirmations(&self) -> U16 {
+        self.request_confirmations.get()
+    }
+
+    pub fn num_words(&self) -> U32 {
+        self.num_words.get()
+    }
+
+    /// Receive function
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        log(
+            self.vm(),
+            Received {
+                sender: self.vm().msg_sender(),
+                value: self.vm().msg_value(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Fund the VRF budget, kept apart from ticket revenue
+    #[payable]
+    pub fn fund_contract(&mut self) -> Result<(), Vec<u8>> {
+        let amount = self.vm().msg_value();
+        let budget = self.vrf_budget.get();
+        self.vrf_budget.set(budget + amount);
+
+        log(
+            self.vm(),
+            VRFBudgetFunded {
+                funder: self.vm().msg_sender(),
+                amount,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+impl Lotto {
+    /// Runs `f` holding the reentrancy guard
+    fn nonreentrant<T, E: From<reentrancy_guard::Error>>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.reentrancy.enter()?;
+        let result = f(self);
+        self.reentrancy.exit();
+        result
+    }
+}
+
+/// Validates a pick of K distinct numbers from 1..N and packs it into a bitmask
+fn numbers_to_mask(numbers: &[u8], pick_count: u8, max_number: u8) -> Result<U256, Error> {
+    if numbers.len() != pick_count as usize {
+        return Err(Error::InvalidPickCount(InvalidPickCount {
+            picked: U256::from(numbers.len()),
+            required: pick_count,
+        }));
+    }
+
+    let mut mask = U256::ZERO;
+    for &number in numbers {
+        if number == 0 || number > max_number {
+            return Err(Error::InvalidNumber(InvalidNumber {
+                number,
+                maxNumber: max_number,
+            }));
+        }
+        if mask.bit(number as usize) {
+            return Err(Error::DuplicateNumber(DuplicateNumber { number }));
+        }
+        mask.set_bit(number as usize, true);
+    }
+
+    Ok(mask)
+}
+
+/// Unpacks a number bitmask into its ascending list of numbers
+fn mask_to_numbers(mask: U256) -> Vec<u8> {
+    (1..=MAX_NUMBER_LIMIT)
+        .filter(|&number| mask.bit(number as usize))
+        .collect()
+}
+
+/// Derives an independent random word from a VRF word and a nonce
+fn expand_random_word(random_word: U256, nonce: u64) -> U256 {
+    let mut input = [0u8; 40];
+    input[..32].copy_from_slice(&random_word.to_be_bytes::<32>());
+    input[32..].copy_from_slice(&nonce.to_be_bytes());
+    U256::from_be_bytes(keccak256(input).0)
+}
+
+fn get_extra_args_for_native_payment() -> Bytes {
+    let mut extra_args_vec = Vec::new();
+    extra_args_vec.extend_from_slice(&[0x92, 0xfd, 0x13, 0x38]);
+    extra_args_vec.extend_from_slice(&[0x00; 28]);
+    extra_args_vec.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+    extra_args_vec.extend_from_slice(&[0x00; 28]);
+    Bytes::from(extra_args_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_sol_types::{sol, SolCall};
+    use motsu::prelude::{Balance, Contract, Funding};
+    use stylus_sdk::abi::Bytes as AbiBytes;
+    use test_contracts::{MockVrfWrapper, Receiver};
+
+    use super::*;
+
+    const TICKET_PRICE: U256 = U256::from_limbs([1_000, 0, 0, 0]);
+    const PICK_COUNT: u8 = 3;
+    const MAX_NUMBER: u8 = 6;
+
+    sol! {
+        function enter(uint8[] numbers) external payable returns (uint256);
+        function claimPrize(uint256 ticketId) external returns (uint256);
+        function claimRefund(uint256 round) external returns (uint256);
+    }
+
+    /// Deploys a 3-of-6 lotto on `wrapper`, owned by `alice`
+    fn deploy(contract: &Contract<Lotto>, wrapper: &Contract<MockVrfWrapper>, alice: Address) {
+        contract
+            .sender(alice)
+            .constructor(
+                wrapper.address(),
+                TICKET_PRICE,
+                PICK_COUNT,
+                MAX_NUMBER,
+                alice,
+            )
+            .unwrap();
+    }
+
+    /// Has `player` buy a ticket for `numbers`; `funder` pays for it
+    fn enter_player(
+        contract: &Contract<Lotto>,
+        player: &Contract<Receiver>,
+        funder: Address,
+        numbers: &[u8],
+    ) {
+        funder.fund(TICKET_PRICE);
+        player.sender(funder).set_target(contract.address());
+        let call = enterCall {
+            numbers: numbers.to_vec(),
+        };
+        player
+            .sender_and_value(funder, TICKET_PRICE)
+            .forward(call.abi_encode().into())
+            .unwrap();
+    }
+
+    /// Starts the draw of the current round and delivers `random_word`. Returns the
+    /// round.
+    fn draw(
+        contract: &Contract<Lotto>,
+        wrapper: &Contract<MockVrfWrapper>,
+        owner: Address,
+        random_word: U256,
+    ) -> U256 {
+        let round = contract.sender(owner).get_current_round();
+        let request_id = contract.sender(owner).start_draw().unwrap();
+        contract
+            .sender(wrapper.address())
+            .raw_fulfill_random_words(request_id, vec![random_word])
+            .unwrap();
+        round
+    }
+
+    /// Splits 1..=6 into the numbers `random_word` draws and the ones it doesn't
+    fn split_numbers(
+        contract: &Contract<Lotto>,
+        alice: Address,
+        random_word: U256,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let drawn = contract.sender(alice).draw_numbers(random_word);
+        (1..=MAX_NUMBER).partition(|&number| drawn.bit(number as usize))
+    }
+
+    fn reentrant_call() -> Vec<u8> {
+        Error::ReentrantCall(reentrancy_guard::ReentrancyGuardReentrantCall {}).into()
+    }
+
+    #[motsu::test]
+    fn draw_numbers_picks_k_distinct_numbers_in_range(
+        contract: Contract<Lotto>,
+        wrapper: Contract<MockVrfWrapper>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, alice);
+
+        for seed in 0..50u64 {
+            let random_word = U256::from(seed);
+            let drawn = contract.sender(alice).draw_numbers(random_word);
+            let numbers = mask_to_numbers(drawn);
+
+            assert_eq!(numbers.len(), PICK_COUNT as usize);
+            assert!(numbers
+                .iter()
+                .all(|&number| (1..=MAX_NUMBER).contains(&number)));
+            assert!(!drawn.bit(0));
+            assert_eq!(contract.sender(alice).draw_numbers(random_word), drawn);
+        }
+    }
+
+    #[motsu::test]
+    fn settle_round_pays_each_tier_and_rolls_the_rest_over(
+        contract: Contract<Lotto>,
+        wrapper: Contract<MockVrfWrapper>,
+        jackpot_winner: Contract<Receiver>,
+        runner_up: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, alice);
+        contract
+            .sender(alice)
+            .set_tier_share(3, U256::from(5_000))
+            .unwrap();
+        contract
+            .sender(alice)
+            .set_tier_share(2, U256::from(2_000))
+            .unwrap();
+        contract
+            .sender(alice)
+            .set_tier_share(1, U256::from(1_000))
+            .unwrap();
+        let random_word = U256::from(7);
+        let (hit, miss) = split_numbers(&contract, alice, random_word);
+
+        enter_player(&contract, &jackpot_winner, alice, &hit);
+        enter_player(&contract, &runner_up, alice, &[hit[0], hit[1], miss[0]]);
+        enter_player(&contract, &runner_up, alice, &miss);
+        let round = draw(&contract, &wrapper, alice, random_word);
+
+        assert_eq!(contract.sender(alice).get_drawn_numbers(round), hit);
+        assert!(!contract
+            .sender(alice)
+            .settle_round(round, U256::from(2))
+            .unwrap());
+        assert!(contract
+            .sender(alice)
+            .settle_round(round, U256::from(2))
+            .unwrap());
+
+        // A pot of 3000: the single 3-match ticket takes half, the 2-match ticket a
+        // fifth, and the unclaimed 1-match tier's share rolls over with the rest
+        assert_eq!(
+            contract.sender(alice).get_tier_payout(round, 3),
+            U256::from(1_500)
+        );
+        assert_eq!(
+            contract.sender(alice).get_tier_payout(round, 2),
+            U256::from(600)
+        );
+        assert_eq!(
+            contract.sender(alice).get_tier_winners(round, 0),
+            U256::from(1)
+        );
+        assert_eq!(contract.sender(alice).get_jackpot(), U256::from(900));
+
+        let claim = |ticket_id: u64| claimPrizeCall {
+            ticketId: U256::from(ticket_id),
+        };
+        jackpot_winner
+            .sender(alice)
+            .forward(claim(0).abi_encode().into())
+            .unwrap();
+        runner_up
+            .sender(alice)
+            .forward(claim(1).abi_encode().into())
+            .unwrap();
+        assert_eq!(jackpot_winner.balance(), U256::from(1_500));
+        assert_eq!(runner_up.balance(), U256::from(600));
+
+        let err = contract
+            .sender(jackpot_winner.address())
+            .claim_prize(U256::ZERO)
+            .unwrap_err();
+        assert!(matches!(err, Error::PrizeAlreadyClaimed(_)));
+        let err = contract
+            .sender(runner_up.address())
+            .claim_prize(U256::from(2))
+            .unwrap_err();
+        assert!(matches!(err, Error::NoPrizeForTicket(_)));
+    }
+
+    #[motsu::test]
+    fn jackpot_rolls_over_until_someone_hits_the_top_tier(
+        contract: Contract<Lotto>,
+        wrapper: Contract<MockVrfWrapper>,
+        player: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, alice);
+        contract
+            .sender(alice)
+            .set_tier_share(3, U256::from(10_000))
+            .unwrap();
+        let random_word = U256::from(11);
+        let (hit, miss) = split_numbers(&contract, alice, random_word);
+
+        enter_player(&contract, &player, alice, &miss);
+        let first = draw(&contract, &wrapper, alice, random_word);
+        contract
+            .sender(alice)
+            .settle_round(first, U256::from(10))
+            .unwrap();
+        assert_eq!(contract.sender(alice).get_jackpot(), TICKET_PRICE);
+
+        enter_player(&contract, &player, alice, &hit);
+        let second = draw(&contract, &wrapper, alice, random_word);
+        contract
+            .sender(alice)
+            .settle_round(second, U256::from(10))
+            .unwrap();
+
+        assert_eq!(
+            contract.sender(alice).get_tier_payout(second, 3),
+            TICKET_PRICE * U256::from(2)
+        );
+        assert_eq!(contract.sender(alice).get_jackpot(), U256::ZERO);
+    }
+
+    #[motsu::test]
+    fn cancelled_draw_refunds_its_tickets(
+        contract: Contract<Lotto>,
+        wrapper: Contract<MockVrfWrapper>,
+        player: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, alice);
+        enter_player(&contract, &player, alice, &[1, 2, 3]);
+        let round = contract.sender(alice).get_current_round();
+        let request_id = contract.sender(alice).start_draw().unwrap();
+
+        let err = contract.sender(alice).cancel_draw().unwrap_err();
+        assert!(matches!(err, Error::DrawNotTimedOut(_)));
+        contract.sender(alice).set_draw_timeout(U256::ZERO).unwrap();
+        contract.sender(alice).cancel_draw().unwrap();
+
+        assert!(contract.sender(alice).is_round_cancelled(round));
+        assert_eq!(
+            contract.sender(alice).get_current_round(),
+            round + U256::from(1)
+        );
+        // The late fulfilment is ignored
+        contract
+            .sender(wrapper.address())
+            .raw_fulfill_random_words(request_id, vec![U256::from(7)])
+            .unwrap();
+        assert!(contract.sender(alice).get_drawn_numbers(round).is_empty());
+
+        let refund = claimRefundCall { round }.abi_encode();
+        player.sender(alice).forward(refund.clone().into()).unwrap();
+        assert_eq!(player.balance(), TICKET_PRICE);
+        let err = player.sender(alice).forward(refund.into()).unwrap_err();
+        let expected: Vec<u8> = Error::NoRefundOwed(NoRefundOwed {
+            round,
+            player: player.address(),
+        })
+        .into();
+        assert_eq!(err, expected);
+        let err = contract
+            .sender(alice)
+            .claim_refund(round + U256::from(1))
+            .unwrap_err();
+        assert!(matches!(err, Error::RoundNotCancelled(_)));
+    }
+
+    #[motsu::test]
+    fn pause_scopes_stop_their_own_calls(
+        contract: Contract<Lotto>,
+        wrapper: Contract<MockVrfWrapper>,
+        alice: Address,
+        bob: Address,
+    ) {
+        deploy(&contract, &wrapper, alice);
+        let entries = PauseScope::Entries as u8;
+
+        let err = contract.sender(bob).set_paused(entries, true).unwrap_err();
+        assert!(matches!(err, Error::UnauthorizedAccount(_)));
+        let err = contract.sender(alice).set_paused(3, true).unwrap_err();
+        assert!(matches!(err, Error::InvalidPauseScope(_)));
+
+        contract.sender(alice).set_paused(entries, true).unwrap();
+        assert!(contract.sender(alice).is_paused(entries).unwrap());
+        bob.fund(TICKET_PRICE * U256::from(2));
+        let err = contract
+            .sender_and_value(bob, TICKET_PRICE)
+            .enter(vec![1, 2, 3])
+            .unwrap_err();
+        assert!(matches!(err, Error::EnforcedPause(_)));
+
+        contract.sender(alice).set_paused(entries, false).unwrap();
+        contract
+            .sender_and_value(bob, TICKET_PRICE)
+            .enter(vec![1, 2, 3])
+            .unwrap();
+
+        contract
+            .sender(alice)
+            .set_paused(PauseScope::VrfRequests as u8, true)
+            .unwrap();
+        let err = contract.sender(alice).start_draw().unwrap_err();
+        assert!(matches!(err, Error::EnforcedPause(_)));
+
+        contract
+            .sender(alice)
+            .set_paused(PauseScope::Payouts as u8, true)
+            .unwrap();
+        let err = contract
+            .sender(bob)
+            .claim_refund(U256::from(1))
+            .unwrap_err();
+        assert!(matches!(err, Error::EnforcedPause(_)));
+    }
+
+    #[motsu::test]
+    fn claim_prize_refuses_reentry(
+        contract: Contract<Lotto>,
+        wrapper: Contract<MockVrfWrapper>,
+        player: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, alice);
+        contract
+            .sender(alice)
+            .set_tier_share(3, U256::from(10_000))
+            .unwrap();
+        let random_word = U256::from(7);
+        let (hit, _) = split_numbers(&contract, alice, random_word);
+        enter_player(&contract, &player, alice, &hit);
+        let round = draw(&contract, &wrapper, alice, random_word);
+        contract
+            .sender(alice)
+            .settle_round(round, U256::from(10))
+            .unwrap();
+
+        let claim: AbiBytes = claimPrizeCall {
+            ticketId: U256::ZERO,
+        }
+        .abi_encode()
+        .into();
+        player.sender(alice).set_reentry(claim.clone());
+        player.sender(alice).forward(claim).unwrap();
+
+        assert_eq!(
+            player.sender(alice).reentry_error().to_vec(),
+            reentrant_call()
+        );
+        assert_eq!(player.balance(), TICKET_PRICE);
+    }
+
+    #[motsu::test]
+    fn claim_refund_refuses_reentry(
+        contract: Contract<Lotto>,
+        wrapper: Contract<MockVrfWrapper>,
+        player: Contract<Receiver>,
+        alice: Address,
+    ) {
+        deploy(&contract, &wrapper, alice);
+        enter_player(&contract, &player, alice, &[1, 2, 3]);
+        let round = contract.sender(alice).get_current_round();
+        contract.sender(alice).start_draw().unwrap();
+        contract.sender(alice).set_draw_timeout(U256::ZERO).unwrap();
+        contract.sender(alice).cancel_draw().unwrap();
+
+        let refund: AbiBytes = claimRefundCall { round }.abi_encode().into();
+        player.sender(alice).set_reentry(refund.clone());
+        player.sender(alice).forward(refund).unwrap();
+
+        assert_eq!(
+            player.sender(alice).reentry_error().to_vec(),
+            reentrant_call()
+        );
+        assert_eq!(player.balance(), TICKET_PRICE);
+    }
+}
@@ -0,0 +1,589 @@
+//!
+//! Storage layout snapshots for upgradeable Stylus contracts
+//!
+//! Behind a proxy a contract's fields are the proxy's state, so a new implementation
+//! may only append to the layout. `assert_append_only` derives the slot layout from
+//! the `sol_storage!` and `#[storage]` declarations of the compiled crate root and
+//! fails when it isn't an append-only extension of the recorded snapshot.
+//!
+//! Storage types declared elsewhere (OpenZeppelin and `stylus-common` components) are
+//! taken as opaque blocks of their compiled `REQUIRED_SLOTS`, and the derived layout
+//! must add up to the contract's own `REQUIRED_SLOTS`, so it can't drift from what the
+//! SDK generates.
+//!
+//! After shipping an upgrade that appends fields, record the new layout with
+//! `UPDATE_STORAGE_LAYOUT=1 cargo test storage_layout`.
+//!
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Environment variable that records the current layout as the snapshot
+pub const UPDATE_VAR: &str = "UPDATE_STORAGE_LAYOUT";
+
+/// What a contract's layout test checks
+pub struct Contract<'a> {
+    /// Name of the `#[entrypoint]` storage struct
+    pub name: &'a str,
+    /// Source of the crate root declaring it and the storage structs it uses
+    pub source: &'a str,
+    /// Storage types declared outside `source`, with their `REQUIRED_SLOTS`
+    pub components: &'a [(&'a str, usize)],
+    /// The contract's own `REQUIRED_SLOTS`
+    pub required_slots: usize,
+    /// Recorded layout, usually `storage-layout.json` next to the manifest
+    pub snapshot: &'a Path,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Field {
+    pub label: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub slot: usize,
+    /// Byte offset within the slot, as the SDK passes it to the field
+    pub offset: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TypeLayout {
+    pub slots: usize,
+    pub members: Vec<Field>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Layout {
+    pub contract: String,
+    pub storage: Vec<Field>,
+    /// Structs declared in the source; components stay opaque
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub types: BTreeMap<String, TypeLayout>,
+}
+
+/// Fails unless `contract` extends its snapshot in place. With `UPDATE_STORAGE_LAYOUT`
+/// set, a compatible layout (or the first one) is written as the new snapshot.
+pub fn assert_append_only(contract: &Contract) {
+    check(contract, std::env::var_os(UPDATE_VAR).is_some());
+}
+
+/// Declares the `storage_layout_is_append_only` test of the contract `$name`, declared
+/// in the file the macro is invoked from and recorded in `storage-layout.json` next to
+/// the manifest. `$component`s are the storage types declared elsewhere.
+#[macro_export]
+macro_rules! append_only_test {
+    ($name:ident, [$($component:ident),* $(,)?]) => {
+        #[test]
+        fn storage_layout_is_append_only() {
+            $crate::assert_append_only(&$crate::Contract {
+                name: stringify!($name),
+                source: include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/", file!())),
+                components: &[$((stringify!($component), $component::REQUIRED_SLOTS)),*],
+                required_slots: $name::REQUIRED_SLOTS,
+                snapshot: &::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                    .join("storage-layout.json"),
+            });
+        }
+    };
+}
+
+fn check(contract: &Contract, update: bool) {
+    let (layout, slots, array_elements) = derive_layout(contract);
+    assert_eq!(
+        slots, contract.required_slots,
+        "derived layout of {} takes {slots} slots, but the contract's REQUIRED_SLOTS is {}",
+        contract.name, contract.required_slots,
+    );
+
+    let json = serde_json::to_string_pretty(&layout).unwrap() + "\n";
+    let Ok(recorded) = fs::read_to_string(contract.snapshot) else {
+        assert!(
+            update,
+            "no storage layout snapshot at {}; record one with {UPDATE_VAR}=1",
+            contract.snapshot.display(),
+        );
+        fs::write(contract.snapshot, json).unwrap();
+        return;
+    };
+    let recorded: Layout = serde_json::from_str(&recorded).expect("valid snapshot");
+
+    let problems = incompatibilities(&recorded, &layout, &array_elements);
+    assert!(
+        problems.is_empty(),
+        "storage layout is not an append-only extension of {}:\n  {}",
+        contract.snapshot.display(),
+        problems.join("\n  "),
+    );
+    if update {
+        fs::write(contract.snapshot, json).unwrap();
+    }
+}
+
+/// Layout of `contract`, its `REQUIRED_SLOTS` and the structs stored in arrays
+pub fn derive_layout(contract: &Contract) -> (Layout, usize, BTreeSet<String>) {
+    let decls = parse_declarations(contract.source);
+    let fields = decls
+        .structs
+        .get(contract.name)
+        .unwrap_or_else(|| panic!("no storage struct `{}` in the source", contract.name));
+    let mut builder = LayoutBuilder {
+        decls: &decls,
+        components: contract.components,
+        types: BTreeMap::new(),
+        array_elements: BTreeSet::new(),
+    };
+    let (storage, slots) = builder.place(fields);
+    let layout = Layout {
+        contract: contract.name.to_string(),
+        storage,
+        types: builder.types,
+    };
+    (layout, slots, builder.array_elements)
+}
+
+/// Every way `new` fails to extend `old` in place
+pub fn incompatibilities(
+    old: &Layout,
+    new: &Layout,
+    array_elements: &BTreeSet<String>,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    check_fields(&old.contract, &old.storage, &new.storage, &mut problems);
+    for (name, old_type) in &old.types {
+        let Some(new_type) = new.types.get(name) else {
+            continue;
+        };
+        check_fields(name, &old_type.members, &new_type.members, &mut problems);
+        if old_type.slots != new_type.slots && array_elements.contains(name) {
+            problems.push(format!(
+                "{name}: stored in an array, so it must keep its {} slots (now {})",
+                old_type.slots, new_type.slots,
+            ));
+        }
+    }
+    problems
+}
+
+/// Flags every field of `old` that `new` doesn't keep in place. Renaming a field
+/// keeps its slot, so only the type and position have to match.
+fn check_fields(scope: &str, old: &[Field], new: &[Field], problems: &mut Vec<String>) {
+    for (i, field) in old.iter().enumerate() {
+        match new.get(i) {
+            Some(current)
+                if (&current.ty, current.slot, current.offset)
+                    == (&field.ty, field.slot, field.offset) => {}
+            Some(current) => problems.push(format!(
+                "{scope}: field #{i} was `{} {}` at slot {} offset {}, now `{} {}` at slot {} offset {}",
+                field.ty,
+                field.label,
+                field.slot,
+                field.offset,
+                current.ty,
+                current.label,
+                current.slot,
+                current.offset,
+            )),
+            None => problems.push(format!("{scope}: field `{}` was removed", field.label)),
+        }
+    }
+}
+
+/// Struct declarations found in the source, by name
+struct Declarations {
+    structs: HashMap<String, Vec<(String, String)>>,
+    consts: HashMap<String, usize>,
+}
+
+struct LayoutBuilder<'a> {
+    decls: &'a Declarations,
+    components: &'a [(&'a str, usize)],
+    types: BTreeMap<String, TypeLayout>,
+    /// Structs stored back to back in arrays, whose size can't change
+    array_elements: BTreeSet<String>,
+}
+
+impl LayoutBuilder<'_> {
+    /// `(SLOT_BYTES, REQUIRED_SLOTS)` of a storage type, as `StorageType` defines them
+    fn size_of(&mut self, ty: &str) -> (usize, usize) {
+        if let Some(bytes) = primitive_bytes(ty) {
+            return (bytes, 0);
+        }
+        if let Some(inner) = ty
+            .strip_prefix("mapping(")
+            .and_then(|t| t.strip_suffix(')'))
+        {
+            let (_, value) = inner
+                .split_once("=>")
+                .expect("mapping without a value type");
+            self.size_of(value.trim());
+            return (32, 0);
+        }
+        if let Some(element) = ty.strip_suffix("[]") {
+            self.array_element(element);
+            return (32, 0);
+        }
+        if let Some((outer, args)) = generic_args(ty) {
+            match outer {
+                "StorageMap" => {
+                    self.size_of(args[1]);
+                    return (32, 0);
+                }
+                "StorageVec" => {
+                    self.array_element(args[0]);
+                    return (32, 0);
+                }
+                "StorageArray" => {
+                    let (bytes, words) = self.array_element(args[0]);
+                    let len = self.eval(args[1]);
+                    let packed = len.div_ceil(32 / bytes);
+                    return (32, (len * words).max(packed));
+                }
+                _ => panic!("unsupported storage type `{ty}`"),
+            }
+        }
+        if matches!(ty, "bytes" | "string" | "StorageBytes" | "StorageString") {
+            return (32, 0);
+        }
+        if let Some(&(_, slots)) = self.components.iter().find(|(name, _)| *name == ty) {
+            return (32, slots);
+        }
+        if !self.types.contains_key(ty) {
+            let fields =
+                self.decls.structs.get(ty).unwrap_or_else(|| {
+                    panic!("unknown storage type `{ty}`; pass it as a component")
+                });
+            let (members, slots) = self.place(fields);
+            self.types
+                .insert(ty.to_string(), TypeLayout { slots, members });
+        }
+        (32, self.types[ty].slots)
+    }
+
+    fn array_element(&mut self, ty: &str) -> (usize, usize) {
+        if self.decls.structs.contains_key(ty) {
+            self.array_elements.insert(ty.to_string());
+        }
+        self.size_of(ty)
+    }
+
+    /// Assigns slots to `fields` the way `#[storage]` does, returning them with the
+    /// struct's `REQUIRED_SLOTS`
+    fn place(&mut self, fields: &[(String, String)]) -> (Vec<Field>, usize) {
+        let mut placed = Vec::new();
+        let (mut slot, mut space) = (0, 32);
+        let (mut total, mut total_space) = (0, 32);
+        for (label, ty) in fields {
+            let (bytes, words) = self.size_of(ty);
+            if space < bytes {
+                space = 32;
+                slot += 1;
+            }
+            space -= bytes;
+            placed.push(Field {
+                label: label.clone(),
+                ty: ty.clone(),
+                slot,
+                offset: space,
+            });
+            if words > 0 {
+                slot += words;
+                space = 32;
+            }
+
+            // `required_slots` keeps its own count, which skips the partially
+            // filled word before a multi-slot field
+            if words > 0 {
+                total += words;
+                total_space = 32;
+            } else {
+                if total_space < bytes {
+                    total_space = 32;
+                    total += 1;
+                }
+                total_space -= bytes;
+            }
+        }
+        if total_space != 32 || total == 0 {
+            total += 1;
+        }
+        (placed, total)
+    }
+
+    fn eval(&self, expr: &str) -> usize {
+        let expr = expr.trim_start_matches('{').trim_end_matches('}');
+        let mut tokens = expr.split_whitespace();
+        let mut value = self.operand(tokens.next().expect("empty length"));
+        while let (Some(op), Some(rhs)) = (tokens.next(), tokens.next()) {
+            let rhs = self.operand(rhs);
+            value = match op {
+                "+" => value + rhs,
+                "-" => value - rhs,
+                "*" => value * rhs,
+                _ => panic!("unsupported operator `{op}` in `{expr}`"),
+            };
+        }
+        value
+    }
+
+    fn operand(&self, token: &str) -> usize {
+        token
+            .parse()
+            .ok()
+            .or_else(|| self.decls.consts.get(token).copied())
+            .unwrap_or_else(|| panic!("unknown constant `{token}`"))
+    }
+}
+
+/// Width of a value type that packs into a slot, for both Solidity and SDK names
+fn primitive_bytes(ty: &str) -> Option<usize> {
+    match ty {
+        "address" | "StorageAddress" => return Some(20),
+        "bool" | "StorageBool" => return Some(1),
+        "uint" | "int" => return Some(32),
+        _ => {}
+    }
+    let bits = ["uint", "int", "StorageU", "StorageI", "StorageB"]
+        .iter()
+        .find_map(|prefix| ty.strip_prefix(prefix)?.parse::<usize>().ok());
+    if let Some(bits) = bits {
+        return Some(bits / 8);
+    }
+    ty.strip_prefix("bytes")?.parse().ok()
+}
+
+/// Splits `Outer<A, B>` into `Outer` and its top-level arguments
+fn generic_args(ty: &str) -> Option<(&str, Vec<&str>)> {
+    let (outer, rest) = ty.split_once('<')?;
+    let inner = rest.strip_suffix('>')?;
+    Some((outer.trim(), split_top_level(inner, ',')))
+}
+
+fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' | '(' | '{' | '[' => depth += 1,
+            '>' | ')' | '}' | ']' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn strip_comments(src: &str) -> String {
+    src.lines()
+        .map(|line| line.split_once("//").map_or(line, |(code, _)| code))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Body of the brace-delimited block opening at or after `from`, and where it ends
+fn block(src: &str, from: usize) -> (&str, usize) {
+    let open = from + src[from..].find('{').expect("missing `{`");
+    let mut depth = 0;
+    for (i, c) in src[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (&src[open + 1..open + i], open + i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    panic!("unbalanced braces");
+}
+
+/// Name of the struct declared at `from` and the position of its body
+fn struct_name(src: &str, from: usize) -> (String, usize) {
+    let start = from + src[from..].find("struct ").expect("missing struct") + "struct ".len();
+    let name = src[start..]
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap();
+    (name.to_string(), start)
+}
+
+fn parse_declarations(src: &str) -> Declarations {
+    let mut decls = Declarations {
+        structs: HashMap::new(),
+        consts: HashMap::new(),
+    };
+    let src = strip_comments(src);
+    for line in src.lines() {
+        let Some(rest) = line
+            .trim()
+            .trim_start_matches("pub ")
+            .strip_prefix("const ")
+        else {
+            continue;
+        };
+        if let Some((name, value)) = rest
+            .split_once(": usize =")
+            .and_then(|(name, value)| Some((name, value.trim().strip_suffix(';')?)))
+        {
+            if let Ok(value) = value.trim().replace('_', "").parse() {
+                decls.consts.insert(name.trim().to_string(), value);
+            }
+        }
+    }
+
+    // Solidity-style declarations: `type name;`
+    let mut from = 0;
+    while let Some(at) = src[from..].find("sol_storage!") {
+        let (body, end) = block(&src, from + at);
+        let mut pos = 0;
+        while let Some(at) = body[pos..].find("struct ") {
+            let (name, start) = struct_name(body, pos + at);
+            let (fields, end) = block(body, start);
+            let fields = fields
+                .split(';')
+                .map(|field| field.split_whitespace().collect::<Vec<_>>())
+                .filter(|tokens| !tokens.is_empty())
+                .map(|tokens| {
+                    let (label, ty) = tokens.split_last().unwrap();
+                    (
+                        label.to_string(),
+                        ty.join(" ").replace("( ", "(").replace(" )", ")"),
+                    )
+                })
+                .collect();
+            decls.structs.insert(name, fields);
+            pos = end;
+        }
+        from = end;
+    }
+
+    // Rust declarations: `name: Type,` under `#[storage]`
+    let mut from = 0;
+    while let Some(at) = src[from..].find("#[storage]") {
+        let (name, start) = struct_name(&src, from + at);
+        let (body, end) = block(&src, start);
+        let fields = split_top_level(body, ',')
+            .into_iter()
+            .map(|field| {
+                let (label, ty) = field.split_once(": ").expect("field without a type");
+                let label = label.split_whitespace().last().unwrap();
+                (
+                    label.to_string(),
+                    ty.split_whitespace().collect::<Vec<_>>().join(" "),
+                )
+            })
+            .collect();
+        decls.structs.insert(name, fields);
+        from = end;
+    }
+    decls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+        sol_storage! {
+            #[entrypoint]
+            pub struct Example {
+                address owner;
+                bool active;
+                uint256 total;
+                Guard guard;
+                Entry[] entries;
+                Component component;
+            }
+
+            pub struct Entry {
+                address player;
+                uint64 tickets;
+            }
+        }
+
+        #[storage]
+        pub struct Guard {
+            entered: StorageBool,
+            __gap: StorageArray<StorageU256, { SLOTS - 1 }>,
+        }
+
+        const SLOTS: usize = 10;
+    "#;
+
+    fn contract<'a>(snapshot: &'a Path, required_slots: usize) -> Contract<'a> {
+        Contract {
+            name: "Example",
+            source: SOURCE,
+            components: &[("Component", 2)],
+            required_slots,
+            snapshot,
+        }
+    }
+
+    #[test]
+    fn packs_fields_like_the_sdk() {
+        let (layout, slots, array_elements) = derive_layout(&contract(Path::new(""), 0));
+        let positions: Vec<_> = layout
+            .storage
+            .iter()
+            .map(|field| (field.label.as_str(), field.slot, field.offset))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                ("owner", 0, 12),
+                ("active", 0, 11),
+                ("total", 1, 0),
+                ("guard", 2, 0),
+                ("entries", 11, 0),
+                ("component", 12, 0),
+            ]
+        );
+        // The SDK's count skips partly filled words before multi-slot fields
+        assert_eq!(slots, 12);
+        assert_eq!(layout.types["Guard"].slots, 9);
+        assert_eq!(layout.types["Entry"].slots, 1);
+        assert!(array_elements.contains("Entry"));
+    }
+
+    #[test]
+    fn allows_appending_and_renaming_but_not_moving() {
+        let (old, _, array_elements) = derive_layout(&contract(Path::new(""), 0));
+        let mut new = derive_layout(&contract(Path::new(""), 0)).0;
+        new.storage[1].label = "deprecated_active".to_string();
+        new.storage.push(Field {
+            label: "appended".to_string(),
+            ty: "uint256".to_string(),
+            slot: 14,
+            offset: 0,
+        });
+        assert!(incompatibilities(&old, &new, &array_elements).is_empty());
+
+        new.storage.remove(1);
+        assert!(!incompatibilities(&old, &new, &array_elements).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "no storage layout snapshot")]
+    fn missing_snapshot_fails() {
+        let snapshot = std::env::temp_dir().join("storage-layout-missing.json");
+        let _ = fs::remove_file(&snapshot);
+        check(&contract(&snapshot, 12), false);
+    }
+
+    #[test]
+    #[should_panic(expected = "REQUIRED_SLOTS")]
+    fn slot_count_must_match_the_compiled_contract() {
+        check(&contract(Path::new(""), 11), false);
+    }
+}
@@ -0,0 +1,12 @@
+//!
+//! Building blocks shared by the Lottery, Lotto and DirectFundingConsumer contracts
+//!
+
+#![cfg_attr(not(test), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+pub mod pause;
+pub mod reentrancy_guard;
+pub mod timelock;
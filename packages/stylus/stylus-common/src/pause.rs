@@ -0,0 +1,32 @@
+//!
+//! Emergency pause scopes
+//!
+//! Each contract keeps one `Pausable` per scope it supports and flips them through
+//! `set_paused(scope, paused)`, so guardians and off-chain tooling use the same
+//! numbering everywhere.
+//!
+
+/// What an emergency pause stops, as passed to `set_paused` and logged in `PauseUpdated`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PauseScope {
+    /// Every way of buying tickets
+    Entries = 0,
+    /// New VRF requests; fulfilments of requests in flight are still recorded
+    VrfRequests = 1,
+    /// Settlement, claims, refunds and withdrawals
+    Payouts = 2,
+}
+
+impl TryFrom<u8> for PauseScope {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PauseScope::Entries),
+            1 => Ok(PauseScope::VrfRequests),
+            2 => Ok(PauseScope::Payouts),
+            _ => Err(value),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//!
+//! Reentrancy guard for functions that move value
+//!
+//! The SDK already rejects reentrant calls unless a contract is built with its
+//! `reentrant` feature; the guard keeps value transfers safe whatever the build.
+//! Contracts run guarded functions through a `nonreentrant(|this| ..)` helper that
+//! calls `enter` before the body and `exit` after it.
+//!
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    alloy_sol_types::sol,
+    prelude::*,
+    storage::{StorageArray, StorageBool, StorageU256},
+};
+
+/// Slots the component occupies, so it can grow without moving the fields after it
+const STORAGE_SLOTS: usize = 50;
+
+sol! {
+    #[derive(Debug)]
+    error ReentrancyGuardReentrantCall();
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Error {
+    ReentrantCall(ReentrancyGuardReentrantCall),
+}
+
+#[storage]
+pub struct ReentrancyGuard {
+    entered: StorageBool,
+    /// Reserved for new fields; shrink it by as many slots as they take
+    __gap: StorageArray<StorageU256, { STORAGE_SLOTS - 1 }>,
+}
+
+impl ReentrancyGuard {
+    pub fn enter(&mut self) -> Result<(), Error> {
+        if self.entered.get() {
+            return Err(Error::ReentrantCall(ReentrancyGuardReentrantCall {}));
+        }
+        self.entered.set(true);
+        Ok(())
+    }
+
+    pub fn exit(&mut self) {
+        self.entered.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stylus_sdk::testing::TestVM;
+
+    use super::*;
+
+    #[test]
+    fn rejects_entering_twice() {
+        let vm = TestVM::default();
+        let mut guard = ReentrancyGuard::from(&vm);
+
+        guard.enter().unwrap();
+        assert!(matches!(guard.enter(), Err(Error::ReentrantCall(_))));
+    }
+
+    #[test]
+    fn can_enter_again_after_exit() {
+        let vm = TestVM::default();
+        let mut guard = ReentrancyGuard::from(&vm);
+
+        guard.enter().unwrap();
+        guard.exit();
+        guard.enter().unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+//!
+//! Timelock for sensitive admin operations
+//!
+//! An operation is identified by `keccak256(selector ++ abi-encoded params)` of the
+//! call it authorizes. It is queued with an ETA at least `min_delay` ahead, and the
+//! guarded function consumes it between the ETA and `ETA + GRACE_PERIOD`.
+//!
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    alloy_primitives::{keccak256, B256, U256},
+    alloy_sol_types::sol,
+    prelude::*,
+    storage::{StorageArray, StorageMap, StorageU256},
+    stylus_core::log,
+};
+
+/// Slots the component occupies, so it can grow without moving the fields after it
+const STORAGE_SLOTS: usize = 50;
+
+/// How long a ready operation can still be executed before it has to be queued again
+pub const GRACE_PERIOD: u64 = 14 * 24 * 60 * 60;
+
+sol! {
+    event OperationQueued(bytes32 indexed id, uint256 eta);
+    event OperationExecuted(bytes32 indexed id);
+    event OperationCancelled(bytes32 indexed id);
+    event MinDelayUpdated(uint256 oldDelay, uint256 newDelay);
+}
+
+sol! {
+    #[derive(Debug)]
+    error TimelockInsufficientDelay(uint256 eta, uint256 earliest);
+
+    #[derive(Debug)]
+    error TimelockAlreadyQueued(bytes32 id);
+
+    #[derive(Debug)]
+    error TimelockNotQueued(bytes32 id);
+
+    #[derive(Debug)]
+    error TimelockNotReady(bytes32 id, uint256 eta);
+
+    #[derive(Debug)]
+    error TimelockExpired(bytes32 id, uint256 eta);
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Error {
+    InsufficientDelay(TimelockInsufficientDelay),
+    AlreadyQueued(TimelockAlreadyQueued),
+    NotQueued(TimelockNotQueued),
+    NotReady(TimelockNotReady),
+    Expired(TimelockExpired),
+}
+
+#[storage]
+pub struct Timelock {
+    /// ETA of every queued operation; zero when not queued
+    etas: StorageMap<B256, StorageU256>,
+    min_delay: StorageU256,
+    /// Reserved for new fields; shrink it by as many slots as they take
+    __gap: StorageArray<StorageU256, { STORAGE_SLOTS - 2 }>,
+}
+
+impl Timelock {
+    /// Id of the operation calling `selector` with `params`
+    pub fn operation_id(selector: [u8; 4], params: &[u8]) -> B256 {
+        keccak256([selector.as_slice(), params].concat())
+    }
+
+    pub fn min_delay(&self) -> U256 {
+        self.min_delay.get()
+    }
+
+    pub fn eta(&self, id: B256) -> U256 {
+        self.etas.get(id)
+    }
+
+    pub fn set_min_delay(&mut self, new_delay: U256) {
+        let old_delay = self.min_delay.get();
+        self.min_delay.set(new_delay);
+
+        log(
+            self.vm(),
+            MinDelayUpdated {
+                oldDelay: old_delay,
+                newDelay: new_delay,
+            },
+        );
+    }
+
+    pub fn queue(&mut self, id: B256, eta: U256) -> Result<(), Error> {
+        if !self.etas.get(id).is_zero() {
+            return Err(Error::AlreadyQueued(TimelockAlreadyQueued { id }));
+        }
+        let earliest = U256::from(self.vm().block_timestamp()) + self.min_delay.get();
+        if eta < earliest {
+            return Err(Error::InsufficientDelay(TimelockInsufficientDelay {
+                eta,
+                earliest,
+            }));
+        }
+
+        self.etas.insert(id, eta);
+        log(self.vm(), OperationQueued { id, eta });
+        Ok(())
+    }
+
+    pub fn cancel(&mut self, id: B256) -> Result<(), Error> {
+        if self.etas.get(id).is_zero() {
+            return Err(Error::NotQueued(TimelockNotQueued { id }));
+        }
+
+        self.etas.insert(id, U256::ZERO);
+        log(self.vm(), OperationCancelled { id });
+        Ok(())
+    }
+
+    /// Marks a ready operation as executed; the caller then applies it
+    pub fn execute(&mut self, id: B256) -> Result<(), Error> {
+        let eta = self.etas.get(id);
+        if eta.is_zero() {
+            return Err(Error::NotQueued(TimelockNotQueued { id }));
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        if now < eta {
+            return Err(Error::NotReady(TimelockNotReady { id, eta }));
+        }
+        if now > eta + U256::from(GRACE_PERIOD) {
+            return Err(Error::Expired(TimelockExpired { id, eta }));
+        }
+
+        self.etas.insert(id, U256::ZERO);
+        log(self.vm(), OperationExecuted { id });
+        Ok(())
+    }
+}
@@ -0,0 +1,432 @@
+//!
+//! Contracts the motsu tests deploy next to the lottery and the VRF consumer
+//!
+//! `Receiver` plays a malicious player or treasurer: it calls the contract under test
+//! and, whenever that contract pays it or sends it an NFT, calls it back. The revert
+//! data of that reentrant call is kept, so a test can check the guard refused it.
+//! `MockVrfWrapper` stands in for the Chainlink VRF wrapper and calls its requester
+//! back the same way when paid for a request; tests fulfil its requests by calling
+//! `raw_fulfill_random_words` as the wrapper. `MockErc721` only implements what an
+//! NFT prize needs: `safeTransferFrom` with its receiver callback. `NextImplementation`
+//! is the logic contract an upgrade moves a proxy to.
+//!
+//! `owner_and_upgrade_tests!` declares the tests every upgradeable contract shares.
+//!
+
+extern crate alloc;
+
+use alloy_primitives::{b256, Address, FixedBytes, B256, U256};
+use alloy_sol_types::SolValue;
+use stylus_sdk::{
+    abi::Bytes,
+    function_selector,
+    prelude::*,
+    storage::{StorageAddress, StorageBool, StorageBytes, StorageMap, StorageU256},
+    stylus_core::{
+        calls::{context::Call, errors::Error as CallError},
+        host::Host,
+    },
+};
+
+const ON_ERC721_RECEIVED: [u8; 4] =
+    function_selector!("onERC721Received", Address, Address, U256, Bytes);
+
+/// ERC-1967 implementation slot, `keccak256("eip1967.proxy.implementation") - 1`
+const IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+/// Calldata calling `selector` with the ABI-encoded `params`
+pub fn calldata(selector: [u8; 4], params: &[u8]) -> Bytes {
+    [selector.as_slice(), params].concat().into()
+}
+
+/// Calls `target` back with `reentry`, unless it is empty, and returns the revert data
+/// if that call reverted
+fn reenter(vm: &dyn Host, target: Address, reentry: &[u8]) -> Option<Vec<u8>> {
+    if reentry.is_empty() {
+        return None;
+    }
+    match vm.call(&Call::new(), target, reentry) {
+        Err(CallError::Revert(data)) => Some(data),
+        _ => None,
+    }
+}
+
+/// Player contract that forwards calls to its target and calls it back when paid
+#[storage]
+pub struct Receiver {
+    target: StorageAddress,
+    /// Calldata sent back to the target on every payment, none when empty
+    reentry: StorageBytes,
+    /// Revert data of the last reentrant call
+    reentry_error: StorageBytes,
+    /// Reverts plain payments, like a contract without a receive function
+    rejects_payments: StorageBool,
+}
+
+unsafe impl TopLevelStorage for Receiver {}
+
+#[public]
+impl Receiver {
+    pub fn set_target(&mut self, target: Address) {
+        self.target.set(target);
+    }
+
+    pub fn set_reentry(&mut self, calldata: Bytes) {
+        self.reentry.set_bytes(calldata);
+    }
+
+    pub fn set_rejects_payments(&mut self, rejects: bool) {
+        self.rejects_payments.set(rejects);
+    }
+
+    pub fn reentry_error(&self) -> Bytes {
+        self.reentry_error.get_bytes().into()
+    }
+
+    /// Calls the target with `calldata` and the value sent along
+    #[payable]
+    pub fn forward(&mut self, calldata: Bytes) -> Result<Bytes, Vec<u8>> {
+        let value = self.vm().msg_value();
+        let target = self.target.get();
+        Ok(self
+            .vm()
+            .call(&Call::new().value(value), target, &calldata)?
+            .into())
+    }
+
+    #[receive]
+    #[payable]
+    pub fn receive(&mut self) -> Result<(), Vec<u8>> {
+        if self.rejects_payments.get() {
+            return Err(b"payment rejected".to_vec());
+        }
+        self.reenter();
+        Ok(())
+    }
+
+    #[selector(name = "onERC721Received")]
+    pub fn on_erc721_received(
+        &mut self,
+        _operator: Address,
+        _from: Address,
+        _token_id: U256,
+        _data: Bytes,
+    ) -> FixedBytes<4> {
+        self.reenter();
+        FixedBytes(ON_ERC721_RECEIVED)
+    }
+}
+
+impl Receiver {
+    fn reenter(&mut self) {
+        let target = self.target.get();
+        if let Some(data) = reenter(self.vm(), target, &self.reentry.get_bytes()) {
+            self.reentry_error.set_bytes(data);
+        }
+    }
+}
+
+/// VRF wrapper charging a fixed price and numbering requests from one
+#[storage]
+pub struct MockVrfWrapper {
+    price: StorageU256,
+    last_request_id: StorageU256,
+    /// Calldata sent back to the requester with every request, none when empty
+    reentry: StorageBytes,
+    /// Revert data of the last reentrant call
+    reentry_error: StorageBytes,
+}
+
+unsafe impl TopLevelStorage for MockVrfWrapper {}
+
+#[public]
+impl MockVrfWrapper {
+    pub fn set_price(&mut self, price: U256) {
+        self.price.set(price);
+    }
+
+    pub fn set_reentry(&mut self, calldata: Bytes) {
+        self.reentry.set_bytes(calldata);
+    }
+
+    pub fn last_request_id(&self) -> U256 {
+        self.last_request_id.get()
+    }
+
+    pub fn reentry_error(&self) -> Bytes {
+        self.reentry_error.get_bytes().into()
+    }
+
+    pub fn calculate_request_price_native(
+        &self,
+        _callback_gas_limit: u32,
+        _num_words: u32,
+    ) -> U256 {
+        self.price.get()
+    }
+
+    #[payable]
+    pub fn request_random_words_in_native(
+        &mut self,
+        _callback_gas_limit: u32,
+        _request_confirmations: u16,
+        _num_words: u32,
+        _extra_args: Bytes,
+    ) -> U256 {
+        let request_id = self.last_request_id.get() + U256::from(1);
+        self.last_request_id.set(request_id);
+        let requester = self.vm().msg_sender();
+        if let Some(data) = reenter(self.vm(), requester, &self.reentry.get_bytes()) {
+            self.reentry_error.set_bytes(data);
+        }
+        request_id
+    }
+}
+
+/// ERC-721 whose transfers only move ownership and call the receiver hook
+#[storage]
+pub struct MockErc721 {
+    owners: StorageMap<U256, StorageAddress>,
+}
+
+unsafe impl TopLevelStorage for MockErc721 {}
+
+#[public]
+impl MockErc721 {
+    pub fn owner_of(&self, token_id: U256) -> Address {
+        self.owners.get(token_id)
+    }
+
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from(
+        &mut self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.owners.insert(token_id, to);
+        let operator = self.vm().msg_sender();
+        let params = (operator, from, token_id, alloy_primitives::Bytes::new()).abi_encode_params();
+        let returned = self
+            .vm()
+            .call(&Call::new(), to, &calldata(ON_ERC721_RECEIVED, &params))?;
+        if !returned.starts_with(&ON_ERC721_RECEIVED) {
+            return Err(b"receiver rejected the token".to_vec());
+        }
+        Ok(())
+    }
+}
+
+/// Logic contract a UUPS upgrade can move to
+///
+/// motsu runs a delegate call against the callee's own storage rather than the
+/// caller's, so the `setVersion` an upgrade delegates to a constructed Stylus
+/// implementation fails its "only through a proxy" check. This one accepts it; the
+/// upgrade's own checks and the proxy's storage are still the real ones.
+#[storage]
+pub struct NextImplementation {}
+
+unsafe impl TopLevelStorage for NextImplementation {}
+
+#[public]
+impl NextImplementation {
+    #[selector(name = "proxiableUUID")]
+    pub fn proxiable_uuid(&self) -> B256 {
+        IMPLEMENTATION_SLOT
+    }
+
+    pub fn set_version(&mut self) {}
+}
+
+/// Declares the tests every upgradeable contract here shares: ownership moves the owner
+/// roles, a VRF wrapper migration waits for its own delay, `initialize` runs once and
+/// upgrades wait for the timelock.
+///
+/// Invoke it in the contract's test module with the contract and the arguments its
+/// `constructor` and `initialize` take between the VRF wrapper and the owner. The module
+/// must have `WRAPPER` and the contract's `Error`, `OWNER_ROLES` and `GUARDIAN_ROLE` in
+/// scope.
+#[macro_export]
+macro_rules! owner_and_upgrade_tests {
+    ($contract:ty, ($($arg:expr),* $(,)?)) => {
+        #[motsu::test]
+        fn accept_ownership_moves_owner_roles(
+            contract: Contract<$contract>,
+            alice: Address,
+            bob: Address,
+            guardian: Address,
+        ) {
+            contract
+                .sender(alice)
+                .constructor(WRAPPER, $($arg,)* alice)
+                .unwrap();
+            contract
+                .sender(alice)
+                .grant_role(GUARDIAN_ROLE.into(), guardian)
+                .unwrap();
+
+            contract.sender(alice).transfer_ownership(bob).unwrap();
+            contract.sender(bob).accept_ownership().unwrap();
+
+            assert_eq!(contract.sender(bob).owner(), bob);
+            for role in OWNER_ROLES {
+                assert!(!contract.sender(bob).has_role(role.into(), alice));
+                assert!(contract.sender(bob).has_role(role.into(), bob));
+            }
+            assert!(contract
+                .sender(bob)
+                .has_role(GUARDIAN_ROLE.into(), guardian));
+        }
+
+        #[motsu::test]
+        fn renounce_ownership_drops_owner_roles(contract: Contract<$contract>, alice: Address) {
+            contract
+                .sender(alice)
+                .constructor(WRAPPER, $($arg,)* alice)
+                .unwrap();
+
+            contract.sender(alice).renounce_ownership().unwrap();
+
+            assert_eq!(contract.sender(alice).owner(), Address::ZERO);
+            for role in OWNER_ROLES {
+                assert!(!contract.sender(alice).has_role(role.into(), alice));
+            }
+        }
+
+        #[motsu::test]
+        fn vrf_wrapper_migration_waits_for_the_timelock(
+            contract: Contract<$contract>,
+            alice: Address,
+        ) {
+            contract
+                .sender(alice)
+                .constructor(WRAPPER, $($arg,)* alice)
+                .unwrap();
+            let new_wrapper = Address::repeat_byte(0x44);
+
+            let eta = contract
+                .sender(alice)
+                .propose_vrf_wrapper(new_wrapper)
+                .unwrap();
+            assert_eq!(contract.sender(alice).get_vrf_wrapper_eta(new_wrapper), eta);
+            let err = contract
+                .sender(alice)
+                .execute_vrf_wrapper(new_wrapper)
+                .unwrap_err();
+            assert!(matches!(err, Error::TimelockNotReady(_)));
+
+            contract
+                .sender(alice)
+                .cancel_vrf_wrapper_proposal(new_wrapper)
+                .unwrap();
+            assert_eq!(
+                contract.sender(alice).get_vrf_wrapper_eta(new_wrapper),
+                U256::ZERO
+            );
+            assert_eq!(contract.sender(alice).i_vrf_v2_plus_wrapper(), WRAPPER);
+        }
+
+        #[motsu::test]
+        fn vrf_wrapper_migration_skips_queue_operation(
+            contract: Contract<$contract>,
+            alice: Address,
+        ) {
+            contract
+                .sender(alice)
+                .constructor(WRAPPER, $($arg,)* alice)
+                .unwrap();
+            let new_wrapper = Address::repeat_byte(0x44);
+            let id = contract.sender(alice).hash_operation(
+                B32::from(function_selector!("executeVrfWrapper", Address)),
+                AbiBytes::from((new_wrapper,).abi_encode_params()),
+            );
+
+            // With no timelock delay the migration would be ready straight away
+            contract.sender(alice).timelock.set_min_delay(U256::ZERO);
+            let now = U256::from(contract.sender(alice).vm().block_timestamp());
+            contract.sender(alice).queue_operation(id, now).unwrap();
+            let err = contract
+                .sender(alice)
+                .execute_vrf_wrapper(new_wrapper)
+                .unwrap_err();
+            assert!(matches!(err, Error::VRFWrapperNotProposed(_)));
+            assert_eq!(contract.sender(alice).i_vrf_v2_plus_wrapper(), WRAPPER);
+        }
+
+        #[motsu::test]
+        fn second_initialize_reverts(proxy: Contract<$contract>, alice: Address, bob: Address) {
+            proxy
+                .sender(alice)
+                .initialize(WRAPPER, $($arg,)* alice)
+                .unwrap();
+
+            let err = proxy
+                .sender(bob)
+                .initialize(WRAPPER, $($arg,)* bob)
+                .unwrap_err();
+            let expected: Vec<u8> =
+                Error::InvalidInitialization(uups_upgradeable::InvalidInitialization {}).into();
+            assert_eq!(err, expected);
+            assert_eq!(proxy.sender(bob).owner(), alice);
+        }
+
+        #[motsu::test]
+        fn upgrade_waits_for_the_timelock(contract: Contract<$contract>, alice: Address) {
+            contract
+                .sender(alice)
+                .constructor(WRAPPER, $($arg,)* alice)
+                .unwrap();
+            let new_implementation = Address::repeat_byte(0x55);
+            let id = contract.sender(alice).hash_operation(
+                B32::from(function_selector!("upgradeToAndCall", Address, AbiBytes)),
+                AbiBytes::from((new_implementation, Bytes::new()).abi_encode_params()),
+            );
+
+            let err = contract
+                .sender(alice)
+                .upgrade_to(new_implementation)
+                .unwrap_err();
+            let expected: Vec<u8> =
+                Error::TimelockNotQueued(timelock::TimelockNotQueued { id }).into();
+            assert_eq!(err, expected);
+
+            let eta = U256::from(contract.sender(alice).vm().block_timestamp())
+                + contract.sender(alice).get_timelock_delay();
+            contract.sender(alice).queue_operation(id, eta).unwrap();
+            let err = contract
+                .sender(alice)
+                .upgrade_to(new_implementation)
+                .unwrap_err();
+            let expected: Vec<u8> =
+                Error::TimelockNotReady(timelock::TimelockNotReady { id, eta }).into();
+            assert_eq!(err, expected);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use motsu::prelude::Contract;
+
+    use super::*;
+
+    #[motsu::test]
+    fn receiver_keeps_the_revert_data_of_its_reentry(
+        receiver: Contract<Receiver>,
+        wrapper: Contract<MockVrfWrapper>,
+        alice: Address,
+    ) {
+        receiver.sender(alice).set_target(wrapper.address());
+        receiver
+            .sender(alice)
+            .set_reentry(calldata([0xde, 0xad, 0xbe, 0xef], &[]));
+
+        receiver.sender(alice).receive().unwrap();
+
+        assert!(receiver
+            .sender(alice)
+            .reentry_error()
+            .starts_with(b"function not found"));
+    }
+}
@@ -0,0 +1,62 @@
+//!
+//! ERC-1967 Proxy Contract in Stylus Rust
+//!
+//! Holds the state of an upgradeable `Lottery` or `DirectFundingConsumer` and
+//! delegates every call to the implementation stored in the ERC-1967 slot. Upgrades
+//! are UUPS-style: the implementation's `upgrade_to_and_call` rewrites that slot.
+//!
+
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    abi::Bytes as AbiBytes, alloy_primitives::Address, alloy_sol_types::sol, prelude::*, ArbResult,
+};
+
+use openzeppelin_stylus::proxy::{erc1967::Erc1967Proxy, IProxy};
+
+sol! {
+    #[derive(Debug)]
+    error ProxyMissingInitialization();
+}
+
+#[derive(SolidityError, Debug)]
+pub enum Error {
+    ProxyMissingInitialization(ProxyMissingInitialization),
+}
+
+#[entrypoint]
+#[storage]
+pub struct UpgradeableProxy {
+    erc1967: Erc1967Proxy,
+}
+
+#[public]
+impl UpgradeableProxy {
+    /// Points the proxy at `implementation` and delegate-calls `data` on it, which
+    /// must be the implementation's `initialize` call: an uninitialized proxy could
+    /// be initialized by anyone
+    #[constructor]
+    pub fn constructor(&mut self, implementation: Address, data: AbiBytes) -> Result<(), Vec<u8>> {
+        if data.is_empty() {
+            return Err(Error::ProxyMissingInitialization(ProxyMissingInitialization {}).into());
+        }
+        Ok(self.erc1967.constructor(implementation, &data)?)
+    }
+
+    pub fn implementation(&self) -> Result<Address, Vec<u8>> {
+        self.erc1967.implementation()
+    }
+
+    /// Forwards every other call, with its value, to the implementation
+    #[fallback]
+    #[payable]
+    pub fn fallback(&mut self, calldata: &[u8]) -> ArbResult {
+        unsafe { self.erc1967.do_fallback(calldata) }
+    }
+}
@@ -14,12 +14,15 @@
 #[macro_use]
 extern crate alloc;
 
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
 /// Import items from the SDK. The prelude contains common traits and macros.
 use stylus_sdk::{
-    alloy_primitives::{Address, Bytes, U16, U256, U32},
-    alloy_sol_types::sol,
+    abi::Bytes as AbiBytes,
+    alloy_primitives::{aliases::B32, Address, Bytes, B256, U16, U256, U32},
+    alloy_sol_types::{sol, SolValue},
+    function_selector,
+    keccak_const::Keccak256,
     prelude::*,
     stylus_core::calls::context::Call,
     stylus_core::log,
@@ -29,10 +32,28 @@ use stylus_sdk::{
 #[allow(deprecated)]
 use stylus_sdk::call::Call as OldCall;
 
-/// Import OpenZeppelin Ownable functionality
-use openzeppelin_stylus::access::ownable::{self, Ownable};
+use stylus_common::{
+    pause::PauseScope,
+    reentrancy_guard::{self, ReentrancyGuard},
+    timelock::{self, Timelock},
+};
+
+/// Import OpenZeppelin Ownable2Step, AccessControl, UUPS and Pausable functionality
+use openzeppelin_stylus::{
+    access::{
+        control::{self, AccessControl, IAccessControl},
+        ownable,
+        ownable_two_step::{IOwnable2Step, Ownable2Step},
+    },
+    proxy::utils::{
+        erc1822::IErc1822Proxiable,
+        uups_upgradeable::{self, IUUPSUpgradeable, UUPSUpgradeable},
+    },
+    utils::pausable::{self, IPausable, Pausable},
+};
 
-// Define persistent storage using the Solidity ABI.
+// Define persistent storage using the Solidity ABI. Behind a proxy these slots are
+// the proxy's state, so new fields are only ever appended.
 sol_storage! {
     #[entrypoint]
     pub struct DirectFundingConsumer {
@@ -45,8 +66,18 @@ sol_storage! {
         uint32 callback_gas_limit;
         uint16 request_confirmations;
         uint32 num_words;
-        Ownable ownable;
-
+        Ownable2Step ownable;
+        uint256 vrf_budget_reserve; // balance kept back from withdrawals to pay for requests
+        Pausable requests_pausable; // stops new randomness requests
+        Pausable payouts_pausable; // stops withdrawals
+        AccessControl access; // config admin, treasurer and guardian roles
+        mapping(uint256 => address) s_requests_wrapper; // wrapper that issued each request
+        mapping(address => uint256) vrf_wrapper_retired_until; // grace end of replaced wrappers
+        mapping(address => uint256) vrf_wrapper_proposal_eta; // ETA `propose_vrf_wrapper` queued
+        bool initialized; // set by the constructor or `initialize`
+        UUPSUpgradeable uups; // proxy-side implementation version
+        ReentrancyGuard reentrancy; // held while value leaves the contract
+        Timelock timelock; // queued VRF wrapper migrations and upgrades
     }
 }
 
@@ -68,19 +99,56 @@ sol! {
     event RequestSent(uint256 indexed requestId, uint32 numWords);
     event RequestFulfilled(uint256 indexed requestId, uint256[] randomWords, uint256 payment);
     event Received(address indexed sender, uint256 value);
+    event VRFBudgetReserveUpdated(uint256 oldReserve, uint256 newReserve);
+    event Withdrawn(address indexed to, uint256 amount);
+    event VRFWrapperProposed(address indexed currentWrapper, address indexed newWrapper, uint256 eta);
+    event VRFWrapperProposalCancelled(address indexed newWrapper);
+    event VRFWrapperUpdated(address indexed oldWrapper, address indexed newWrapper, uint256 oldWrapperGraceEnd);
+    event PauseUpdated(uint8 indexed scope, bool paused, address guardian);
 }
 
 // Define custom errors
 sol! {
     #[derive(Debug)]
     error OnlyVRFWrapperCanFulfill(address have, address want);
+
+    #[derive(Debug)]
+    error InvalidVRFWrapper(address wrapper);
+
+    #[derive(Debug)]
+    error VRFWrapperNotProposed(address wrapper);
+
+    #[derive(Debug)]
+    error InsufficientSurplus(uint256 requested, uint256 available);
+
+    #[derive(Debug)]
+    error TransferFailed();
+
+    #[derive(Debug)]
+    error InvalidPauseScope(uint8 scope);
 }
 
 #[derive(SolidityError, Debug)]
 pub enum Error {
     OnlyVRFWrapperCanFulfill(OnlyVRFWrapperCanFulfill),
+    InvalidVRFWrapper(InvalidVRFWrapper),
+    VRFWrapperNotProposed(VRFWrapperNotProposed),
+    InsufficientSurplus(InsufficientSurplus),
+    TransferFailed(TransferFailed),
+    InvalidPauseScope(InvalidPauseScope),
+    EnforcedPause(pausable::EnforcedPause),
+    ExpectedPause(pausable::ExpectedPause),
     UnauthorizedAccount(ownable::OwnableUnauthorizedAccount),
     InvalidOwner(ownable::OwnableInvalidOwner),
+    AccessControlUnauthorizedAccount(control::AccessControlUnauthorizedAccount),
+    AccessControlBadConfirmation(control::AccessControlBadConfirmation),
+    InvalidInitialization(uups_upgradeable::InvalidInitialization),
+    ReentrantCall(reentrancy_guard::ReentrancyGuardReentrantCall),
+    TimelockInsufficientDelay(timelock::TimelockInsufficientDelay),
+    TimelockAlreadyQueued(timelock::TimelockAlreadyQueued),
+    TimelockNotQueued(timelock::TimelockNotQueued),
+    TimelockNotReady(timelock::TimelockNotReady),
+    TimelockExpired(timelock::TimelockExpired),
 }
 
 impl From<ownable::Error> for Error {
@@ -91,22 +159,157 @@ impl From<ownable::Error> for Error {
         }
     }
 }
+
+impl From<control::Error> for Error {
+    fn from(value: control::Error) -> Self {
+        match value {
+            control::Error::UnauthorizedAccount(e) => Error::AccessControlUnauthorizedAccount(e),
+            control::Error::BadConfirmation(e) => Error::AccessControlBadConfirmation(e),
+        }
+    }
+}
+
+impl From<reentrancy_guard::Error> for Error {
+    fn from(value: reentrancy_guard::Error) -> Self {
+        match value {
+            reentrancy_guard::Error::ReentrantCall(e) => Error::ReentrantCall(e),
+        }
+    }
+}
+
+impl From<timelock::Error> for Error {
+    fn from(value: timelock::Error) -> Self {
+        match value {
+            timelock::Error::InsufficientDelay(e) => Error::TimelockInsufficientDelay(e),
+            timelock::Error::AlreadyQueued(e) => Error::TimelockAlreadyQueued(e),
+            timelock::Error::NotQueued(e) => Error::TimelockNotQueued(e),
+            timelock::Error::NotReady(e) => Error::TimelockNotReady(e),
+            timelock::Error::Expired(e) => Error::TimelockExpired(e),
+        }
+    }
+}
+
+impl From<pausable::Error> for Error {
+    fn from(value: pausable::Error) -> Self {
+        match value {
+            pausable::Error::EnforcedPause(e) => Error::EnforcedPause(e),
+            pausable::Error::ExpectedPause(e) => Error::ExpectedPause(e),
+        }
+    }
+}
+
+/// Minimum delay between proposing a new VRF wrapper and switching to it
+const VRF_WRAPPER_MIGRATION_DELAY: u64 = 2 * 24 * 60 * 60;
+
+/// Minimum delay between queueing a timelocked operation, such as an upgrade, and
+/// executing it
+const TIMELOCK_DELAY: u64 = 24 * 60 * 60;
+
+/// How long a replaced VRF wrapper can still fulfil the requests it issued
+const VRF_WRAPPER_GRACE_PERIOD: u64 = 24 * 60 * 60;
+
+/// Role identifiers, shared with the lottery
+pub const CONFIG_ADMIN_ROLE: [u8; 32] = Keccak256::new().update(b"CONFIG_ADMIN_ROLE").finalize();
+pub const TREASURER_ROLE: [u8; 32] = Keccak256::new().update(b"TREASURER_ROLE").finalize();
+pub const GUARDIAN_ROLE: [u8; 32] = Keccak256::new().update(b"GUARDIAN_ROLE").finalize();
+
+/// Roles that go with ownership: granted to the owner on deployment and moved to the
+/// new owner on `accept_ownership`
+const OWNER_ROLES: [[u8; 32]; 4] = [
+    AccessControl::DEFAULT_ADMIN_ROLE,
+    CONFIG_ADMIN_ROLE,
+    TREASURER_ROLE,
+    GUARDIAN_ROLE,
+];
+
 /// Declare that `DirectFundingConsumer` is a contract with the following external methods.
 #[public]
 impl DirectFundingConsumer {
-    /// Constructor - initializes the contract with VRF wrapper address
+    /// Constructor - initializes the contract with VRF wrapper address. Also marks
+    /// the deployment as an implementation, so a direct deployment can't be upgraded.
     #[constructor]
     pub fn constructor(
         &mut self,
         vrf_v2_plus_wrapper: Address,
         owner: Address,
     ) -> Result<(), Error> {
-        self.ownable.constructor(owner)?;
-        self.i_vrf_v2_plus_wrapper.set(vrf_v2_plus_wrapper);
-        self.callback_gas_limit.set(U32::from(100000));
-        self.request_confirmations.set(U16::from(3));
-        self.num_words.set(U32::from(1));
-        Ok(())
+        self.uups.constructor();
+        self.init(vrf_v2_plus_wrapper, owner)
+    }
+
+    /// Initializer replacing the constructor for proxied deployments; the proxy
+    /// delegate-calls it on deployment. Only callable once, and only through a proxy.
+    pub fn initialize(
+        &mut self,
+        vrf_v2_plus_wrapper: Address,
+        owner: Address,
+    ) -> Result<(), Vec<u8>> {
+        self.uups.set_version()?;
+        Ok(self.init(vrf_v2_plus_wrapper, owner)?)
+    }
+
+    /// Owner moves the proxy to `new_implementation`, which must keep this storage
+    /// layout and only append fields. Timelocked like `upgrade_to_and_call` with
+    /// empty `data`.
+    pub fn upgrade_to(&mut self, new_implementation: Address) -> Result<(), Vec<u8>> {
+        self.upgrade_to_and_call(new_implementation, AbiBytes::from(Vec::new()))
+    }
+
+    /// Owner upgrades and delegate-calls `data` on the new implementation.
+    /// Timelocked: queue `upgradeToAndCall(address,bytes)` first.
+    pub fn upgrade_to_and_call(
+        &mut self,
+        new_implementation: Address,
+        data: AbiBytes,
+    ) -> Result<(), Vec<u8>> {
+        self.ownable.ownable.only_owner()?;
+        let id = Timelock::operation_id(
+            function_selector!("upgradeToAndCall", Address, AbiBytes),
+            &(new_implementation, Bytes::copy_from_slice(&data)).abi_encode_params(),
+        );
+        self.timelock.execute(id)?;
+        self.uups.upgrade_to_and_call(new_implementation, data)
+    }
+
+    /// Config admin queues a timelocked operation, identified by
+    /// `hash_operation(selector, params)` of the call it authorizes
+    pub fn queue_operation(&mut self, id: B256, eta: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        Ok(self.timelock.queue(id, eta)?)
+    }
+
+    pub fn cancel_operation(&mut self, id: B256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        Ok(self.timelock.cancel(id)?)
+    }
+
+    /// Operation id of calling `selector` with ABI-encoded `params`
+    pub fn hash_operation(&self, selector: B32, params: AbiBytes) -> B256 {
+        Timelock::operation_id(selector.0, &params)
+    }
+
+    /// ETA of a queued operation; zero when not queued
+    pub fn get_operation_eta(&self, id: B256) -> U256 {
+        self.timelock.eta(id)
+    }
+
+    pub fn get_timelock_delay(&self) -> U256 {
+        self.timelock.min_delay()
+    }
+
+    /// Records this implementation's version in the proxy; called by `upgrade_to_and_call`
+    pub fn set_version(&mut self) -> Result<(), Vec<u8>> {
+        Ok(self.uups.set_version()?)
+    }
+
+    #[selector(name = "proxiableUUID")]
+    pub fn proxiable_uuid(&self) -> Result<B256, Vec<u8>> {
+        self.uups.proxiable_uuid()
+    }
+
+    #[selector(name = "UPGRADE_INTERFACE_VERSION")]
+    pub fn upgrade_interface_version(&self) -> String {
+        self.uups.upgrade_interface_version()
     }
 
     /// Internal function to request randomness paying in native ETH token
@@ -143,39 +346,45 @@ impl DirectFundingConsumer {
             extra_args,
         )?;
 
+        self.s_requests_wrapper
+            .insert(request_id, external_vrf_wrapper_address);
+
         Ok((request_id, request_price))
     }
 
     /// Public function to request random words
     pub fn request_random_words(&mut self) -> Result<U256, Vec<u8>> {
-        let callback_gas_limit = self.callback_gas_limit.get().try_into().unwrap_or(100000);
-        let request_confirmations = self.request_confirmations.get().try_into().unwrap_or(3);
-        let num_words = self.num_words.get().try_into().unwrap_or(1);
-
-        let (request_id, req_price) = self.request_randomness_pay_in_native(
-            callback_gas_limit,
-            request_confirmations,
-            num_words,
-        )?;
-
-        // Store request status in separate mappings
-        self.s_requests_fulfilled.insert(request_id, false);
-        self.s_requests_paid.insert(request_id, req_price);
-
-        // Add to request IDs array and update last request ID
-        self.request_ids.push(request_id);
-        self.last_request_id.set(request_id);
-
-        // Emit event
-        log(
-            self.vm(),
-            RequestSent {
-                requestId: request_id,
-                numWords: num_words,
-            },
-        );
-
-        Ok(request_id)
+        self.nonreentrant(|this| {
+            this.requests_pausable.when_not_paused()?;
+            let callback_gas_limit = this.callback_gas_limit.get().try_into().unwrap_or(100000);
+            let request_confirmations = this.request_confirmations.get().try_into().unwrap_or(3);
+            let num_words = this.num_words.get().try_into().unwrap_or(1);
+
+            let (request_id, req_price) = this.request_randomness_pay_in_native(
+                callback_gas_limit,
+                request_confirmations,
+                num_words,
+            )?;
+
+            // Store request status in separate mappings
+            this.s_requests_fulfilled.insert(request_id, false);
+            this.s_requests_paid.insert(request_id, req_price);
+
+            // Add to request IDs array and update last request ID
+            this.request_ids.push(request_id);
+            this.last_request_id.set(request_id);
+
+            // Emit event
+            log(
+                this.vm(),
+                RequestSent {
+                    requestId: request_id,
+                    numWords: num_words,
+                },
+            );
+
+            Ok(request_id)
+        })
     }
 
     /// View: get the current native price required to request randomness
@@ -233,16 +442,101 @@ impl DirectFundingConsumer {
         request_id: U256,
         random_words: Vec<U256>,
     ) -> Result<(), Error> {
-        let vrf_wrapper_addr = self.i_vrf_v2_plus_wrapper.get();
-        let msg_sender = self.vm().msg_sender();
-        if msg_sender != vrf_wrapper_addr {
-            return Err(Error::OnlyVRFWrapperCanFulfill(OnlyVRFWrapperCanFulfill {
-                have: msg_sender,
-                want: vrf_wrapper_addr,
+        self.only_request_wrapper(request_id)?;
+        self.fulfill_random_words(request_id, random_words)
+    }
+
+    /// Config admin proposes a new VRF wrapper by queueing its migration in the
+    /// timelock; it can take over after `VRF_WRAPPER_MIGRATION_DELAY`. Returns the ETA.
+    pub fn propose_vrf_wrapper(&mut self, new_wrapper: Address) -> Result<U256, Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.check_vrf_wrapper(new_wrapper)?;
+
+        let eta = U256::from(self.vm().block_timestamp() + VRF_WRAPPER_MIGRATION_DELAY);
+        self.timelock
+            .queue(vrf_wrapper_operation_id(new_wrapper), eta)?;
+        self.vrf_wrapper_proposal_eta.insert(new_wrapper, eta);
+
+        log(
+            self.vm(),
+            VRFWrapperProposed {
+                currentWrapper: self.i_vrf_v2_plus_wrapper.get(),
+                newWrapper: new_wrapper,
+                eta,
+            },
+        );
+
+        Ok(eta)
+    }
+
+    pub fn cancel_vrf_wrapper_proposal(&mut self, new_wrapper: Address) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.timelock
+            .cancel(vrf_wrapper_operation_id(new_wrapper))?;
+        self.vrf_wrapper_proposal_eta
+            .insert(new_wrapper, U256::ZERO);
+
+        log(
+            self.vm(),
+            VRFWrapperProposalCancelled {
+                newWrapper: new_wrapper,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Config admin switches to a proposed wrapper once its timelock is ready. The
+    /// old wrapper keeps fulfilling its own requests for `VRF_WRAPPER_GRACE_PERIOD`.
+    /// Only a migration queued by `propose_vrf_wrapper` runs, so `queue_operation`
+    /// can't shorten `VRF_WRAPPER_MIGRATION_DELAY` to the timelock delay.
+    pub fn execute_vrf_wrapper(&mut self, new_wrapper: Address) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        self.check_vrf_wrapper(new_wrapper)?;
+        let id = vrf_wrapper_operation_id(new_wrapper);
+        let eta = self.timelock.eta(id);
+        if !eta.is_zero() && eta != self.vrf_wrapper_proposal_eta.get(new_wrapper) {
+            return Err(Error::VRFWrapperNotProposed(VRFWrapperNotProposed {
+                wrapper: new_wrapper,
             }));
         }
+        self.timelock.execute(id)?;
+        self.vrf_wrapper_proposal_eta
+            .insert(new_wrapper, U256::ZERO);
+
+        let old_wrapper = self.i_vrf_v2_plus_wrapper.get();
+        let grace_end = U256::from(self.vm().block_timestamp() + VRF_WRAPPER_GRACE_PERIOD);
+        self.vrf_wrapper_retired_until
+            .insert(old_wrapper, grace_end);
+        self.vrf_wrapper_retired_until
+            .insert(new_wrapper, U256::ZERO);
+        self.i_vrf_v2_plus_wrapper.set(new_wrapper);
 
-        self.fulfill_random_words(request_id, random_words)
+        log(
+            self.vm(),
+            VRFWrapperUpdated {
+                oldWrapper: old_wrapper,
+                newWrapper: new_wrapper,
+                oldWrapperGraceEnd: grace_end,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Time a proposed wrapper can be executed; zero when it isn't proposed
+    pub fn get_vrf_wrapper_eta(&self, new_wrapper: Address) -> U256 {
+        self.timelock.eta(vrf_wrapper_operation_id(new_wrapper))
+    }
+
+    /// Wrapper that issued a request and is the only one allowed to fulfil it
+    pub fn get_request_wrapper(&self, request_id: U256) -> Address {
+        self.s_requests_wrapper.get(request_id)
+    }
+
+    /// End of a replaced wrapper's fulfilment window; zero for the current wrapper
+    pub fn get_vrf_wrapper_grace_end(&self, wrapper: Address) -> U256 {
+        self.vrf_wrapper_retired_until.get(wrapper)
     }
 
     /// Get the status of a randomness request
@@ -264,21 +558,165 @@ impl DirectFundingConsumer {
         self.last_request_id.get()
     }
 
-    /// Withdraw native tokens
-    pub fn withdraw_native(&mut self, amount: U256) -> Result<(), Vec<u8>> {
-        self.ownable.only_owner()?;
+    /// Balance above the reserved VRF budget
+    pub fn withdrawable_surplus(&self) -> U256 {
+        let balance = self.vm().balance(self.vm().contract_address());
+        balance.saturating_sub(self.vrf_budget_reserve.get())
+    }
+
+    /// Config admin sets the balance kept back from withdrawals to pay for VRF requests
+    pub fn set_vrf_budget_reserve(&mut self, reserve: U256) -> Result<(), Error> {
+        self.access.only_role(CONFIG_ADMIN_ROLE.into())?;
+        let old_reserve = self.vrf_budget_reserve.get();
+        self.vrf_budget_reserve.set(reserve);
+
+        log(
+            self.vm(),
+            VRFBudgetReserveUpdated {
+                oldReserve: old_reserve,
+                newReserve: reserve,
+            },
+        );
+
+        Ok(())
+    }
+
+    pub fn vrf_budget_reserve(&self) -> U256 {
+        self.vrf_budget_reserve.get()
+    }
+
+    /// Treasurer withdraws native tokens, limited to the withdrawable surplus
+    pub fn withdraw_native(&mut self, amount: U256) -> Result<(), Error> {
+        self.nonreentrant(|this| {
+            this.access.only_role(TREASURER_ROLE.into())?;
+            this.payouts_pausable.when_not_paused()?;
+
+            let available = this.withdrawable_surplus();
+            if amount > available {
+                return Err(Error::InsufficientSurplus(InsufficientSurplus {
+                    requested: amount,
+                    available,
+                }));
+            }
+
+            // Transfer the amount
+            let treasurer = this.vm().msg_sender();
+            this.vm()
+                .call(&Call::new().value(amount), treasurer, &[])
+                .map_err(|_| Error::TransferFailed(TransferFailed {}))?;
+
+            log(
+                this.vm(),
+                Withdrawn {
+                    to: treasurer,
+                    amount,
+                },
+            );
+
+            Ok(())
+        })
+    }
 
-        // Transfer the amount
-        self.vm()
-            .call(&Call::new().value(amount), self.ownable.owner(), &[])?;
+    /// Guardian flips the `PauseScope::VrfRequests` switch, which stops new requests
+    /// while fulfilments still get recorded, or the `PauseScope::Payouts` one, which
+    /// stops withdrawals. The consumer sells no entries, so that scope is rejected.
+    pub fn set_paused(&mut self, scope: u8, paused: bool) -> Result<(), Error> {
+        self.access.only_role(GUARDIAN_ROLE.into())?;
+        let pausable = match PauseScope::try_from(scope) {
+            Ok(PauseScope::VrfRequests) => &mut self.requests_pausable,
+            Ok(PauseScope::Payouts) => &mut self.payouts_pausable,
+            _ => return Err(Error::InvalidPauseScope(InvalidPauseScope { scope })),
+        };
+        if paused {
+            pausable.pause()?;
+        } else {
+            pausable.unpause()?;
+        }
+
+        log(
+            self.vm(),
+            PauseUpdated {
+                scope,
+                paused,
+                guardian: self.vm().msg_sender(),
+            },
+        );
 
         Ok(())
     }
 
+    pub fn is_paused(&self, scope: u8) -> Result<bool, Error> {
+        let pausable = match PauseScope::try_from(scope) {
+            Ok(PauseScope::VrfRequests) => &self.requests_pausable,
+            Ok(PauseScope::Payouts) => &self.payouts_pausable,
+            _ => return Err(Error::InvalidPauseScope(InvalidPauseScope { scope })),
+        };
+        Ok(pausable.paused())
+    }
+
     pub fn owner(&self) -> Address {
         self.ownable.owner()
     }
 
+    pub fn pending_owner(&self) -> Address {
+        self.ownable.pending_owner()
+    }
+
+    /// Owner nominates a new owner, who must call `accept_ownership`. Nominating
+    /// again overwrites the pending owner; nominating the zero address cancels.
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Error> {
+        Ok(self.ownable.transfer_ownership(new_owner)?)
+    }
+
+    /// Pending owner takes over, along with the `OWNER_ROLES` the previous owner loses.
+    /// Roles granted to other accounts stay with their holders.
+    pub fn accept_ownership(&mut self) -> Result<(), Error> {
+        let previous_owner = self.ownable.owner();
+        self.ownable.accept_ownership()?;
+
+        let new_owner = self.ownable.owner();
+        for role in OWNER_ROLES {
+            self.access._revoke_role(role.into(), previous_owner);
+            self.access._grant_role(role.into(), new_owner);
+        }
+
+        Ok(())
+    }
+
+    /// Owner gives up ownership and its `OWNER_ROLES`. Roles granted to other
+    /// accounts stay with their holders.
+    pub fn renounce_ownership(&mut self) -> Result<(), Error> {
+        let owner = self.ownable.owner();
+        self.ownable.renounce_ownership()?;
+        for role in OWNER_ROLES {
+            self.access._revoke_role(role.into(), owner);
+        }
+        Ok(())
+    }
+
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        self.access.has_role(role, account)
+    }
+
+    pub fn get_role_admin(&self, role: B256) -> B256 {
+        self.access.get_role_admin(role)
+    }
+
+    /// Default admin grants a role; emits `RoleGranted`
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), Error> {
+        Ok(self.access.grant_role(role, account)?)
+    }
+
+    /// Default admin revokes a role; emits `RoleRevoked`
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), Error> {
+        Ok(self.access.revoke_role(role, account)?)
+    }
+
+    /// Caller drops one of its own roles; `confirmation` must be the caller
+    pub fn renounce_role(&mut self, role: B256, confirmation: Address) -> Result<(), Error> {
+        Ok(self.access.renounce_role(role, confirmation)?)
+    }
+
     // Getter functions for configuration
     pub fn callback_gas_limit(&self) -> U32 {
         self.callback_gas_limit.get()
@@ -311,7 +749,79 @@ impl DirectFundingConsumer {
     }
 }
 
-// Note: We keep ownership management internal through `ownable`.
+impl DirectFundingConsumer {
+    /// Shared by the constructor and `initialize`
+    fn init(&mut self, vrf_v2_plus_wrapper: Address, owner: Address) -> Result<(), Error> {
+        if self.initialized.get() {
+            return Err(Error::InvalidInitialization(
+                uups_upgradeable::InvalidInitialization {},
+            ));
+        }
+        self.initialized.set(true);
+
+        self.ownable.constructor(owner)?;
+        for role in OWNER_ROLES {
+            self.access._grant_role(role.into(), owner);
+        }
+        self.i_vrf_v2_plus_wrapper.set(vrf_v2_plus_wrapper);
+        self.timelock.set_min_delay(U256::from(TIMELOCK_DELAY));
+        self.callback_gas_limit.set(U32::from(100000));
+        self.request_confirmations.set(U16::from(3));
+        self.num_words.set(U32::from(1));
+        Ok(())
+    }
+
+    /// Runs `f` holding the reentrancy guard
+    fn nonreentrant<T, E: From<reentrancy_guard::Error>>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.reentrancy.enter()?;
+        let result = f(self);
+        self.reentrancy.exit();
+        result
+    }
+
+    /// A wrapper can be migrated to unless it is zero or already in use
+    fn check_vrf_wrapper(&self, wrapper: Address) -> Result<(), Error> {
+        if wrapper.is_zero() || wrapper == self.i_vrf_v2_plus_wrapper.get() {
+            return Err(Error::InvalidVRFWrapper(InvalidVRFWrapper { wrapper }));
+        }
+        Ok(())
+    }
+
+    /// Only the wrapper that issued a request may fulfil it, and a replaced wrapper
+    /// only until its grace window closes
+    fn only_request_wrapper(&self, request_id: U256) -> Result<(), Error> {
+        let current_wrapper = self.i_vrf_v2_plus_wrapper.get();
+        let request_wrapper = self.s_requests_wrapper.get(request_id);
+        let msg_sender = self.vm().msg_sender();
+
+        let authorized = if request_wrapper.is_zero() || request_wrapper == current_wrapper {
+            msg_sender == current_wrapper
+        } else {
+            msg_sender == request_wrapper
+                && U256::from(self.vm().block_timestamp())
+                    <= self.vrf_wrapper_retired_until.get(request_wrapper)
+        };
+        if !authorized {
+            return Err(Error::OnlyVRFWrapperCanFulfill(OnlyVRFWrapperCanFulfill {
+                have: msg_sender,
+                want: current_wrapper,
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+/// Timelock operation switching to `new_wrapper`
+fn vrf_wrapper_operation_id(new_wrapper: Address) -> B256 {
+    Timelock::operation_id(
+        function_selector!("executeVrfWrapper", Address),
+        &(new_wrapper,).abi_encode_params(),
+    )
+}
 
 fn get_extra_args_for_native_payment() -> Bytes {
     // Encode extra args according to VRFV2PlusClient._argsToBytes()
@@ -324,3 +834,97 @@ fn get_extra_args_for_native_payment() -> Bytes {
     extra_args_vec.extend_from_slice(&[0x00; 28]); // Final padding
     Bytes::from(extra_args_vec)
 }
+
+#[cfg(test)]
+mod tests {
+    use motsu::prelude::{Balance, Contract, Funding};
+    use test_contracts::{calldata, MockVrfWrapper, Receiver};
+
+    use super::*;
+
+    const WRAPPER: Address = Address::repeat_byte(0x33);
+
+    fn reentrant_call() -> Vec<u8> {
+        Error::ReentrantCall(reentrancy_guard::ReentrancyGuardReentrantCall {}).into()
+    }
+
+    storage_layout::append_only_test!(
+        DirectFundingConsumer,
+        [
+            AccessControl,
+            Ownable2Step,
+            Pausable,
+            ReentrancyGuard,
+            Timelock,
+            UUPSUpgradeable
+        ]
+    );
+
+    test_contracts::owner_and_upgrade_tests!(DirectFundingConsumer, ());
+
+    #[motsu::test]
+    fn withdraw_native_refuses_reentry_from_the_treasurer(
+        contract: Contract<DirectFundingConsumer>,
+        treasurer: Contract<Receiver>,
+        alice: Address,
+    ) {
+        contract.sender(alice).constructor(WRAPPER, alice).unwrap();
+        contract
+            .sender(alice)
+            .grant_role(TREASURER_ROLE.into(), treasurer.address())
+            .unwrap();
+        let amount = U256::from(1_000);
+        alice.fund(amount * U256::from(2));
+        contract
+            .sender_and_value(alice, amount * U256::from(2))
+            .receive()
+            .unwrap();
+
+        // Enough is left for the reentrant withdrawal, so only the guard stops it
+        let withdraw = calldata(
+            function_selector!("withdrawNative", U256),
+            &(amount,).abi_encode_params(),
+        );
+        treasurer.sender(alice).set_target(contract.address());
+        treasurer.sender(alice).set_reentry(withdraw.clone());
+        treasurer.sender(alice).forward(withdraw).unwrap();
+
+        assert_eq!(
+            treasurer.sender(alice).reentry_error().to_vec(),
+            reentrant_call()
+        );
+        assert_eq!(treasurer.balance(), amount);
+        assert_eq!(contract.balance(), amount);
+    }
+
+    #[motsu::test]
+    fn request_random_words_refuses_reentry_from_the_vrf_wrapper(
+        contract: Contract<DirectFundingConsumer>,
+        wrapper: Contract<MockVrfWrapper>,
+        alice: Address,
+    ) {
+        contract
+            .sender(alice)
+            .constructor(wrapper.address(), alice)
+            .unwrap();
+        let price = U256::from(1_000);
+        wrapper.sender(alice).set_price(price);
+        alice.fund(price * U256::from(2));
+        contract
+            .sender_and_value(alice, price * U256::from(2))
+            .receive()
+            .unwrap();
+        wrapper
+            .sender(alice)
+            .set_reentry(calldata(function_selector!("requestRandomWords"), &[]));
+
+        let request_id = contract.sender(alice).request_random_words().unwrap();
+
+        assert_eq!(
+            wrapper.sender(alice).reentry_error().to_vec(),
+            reentrant_call()
+        );
+        assert_eq!(wrapper.sender(alice).last_request_id(), request_id);
+        assert_eq!(wrapper.balance(), price);
+    }
+}
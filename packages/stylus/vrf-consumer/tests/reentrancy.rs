@@ -0,0 +1,161 @@
+//! End-to-end check that a malicious treasurer can't reenter `withdraw_native`
+//! while it is being paid.
+//!
+//! Needs a node (e.g. the nitro devnode) and these environment variables:
+//! - `RPC_URL`, `PRIV_KEY`: the node and a funded key holding the consumer's default
+//!   admin role, e.g. the deployer's
+//! - `CONSUMER_ADDRESS`: a `DirectFundingConsumer` (or its proxy) with payouts unpaused
+//!   and a balance covering its VRF budget reserve
+//!
+//! Run with `cargo test --test reentrancy -- --ignored`.
+
+use std::{str::FromStr, sync::Arc};
+
+use ethers::{
+    middleware::SignerMiddleware,
+    prelude::abigen,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, Bytes, TransactionRequest, H256, U256},
+    utils::{hex, parse_ether},
+};
+use eyre::eyre;
+
+abigen!(
+    DirectFundingConsumer,
+    r#"[
+        function withdrawNative(uint256 amount) external
+        function treasurerRole() external view returns (bytes32)
+        function grantRole(bytes32 role, address account) external
+        function revokeRole(bytes32 role, address account) external
+    ]"#
+);
+
+type Client = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+/// Runtime of the malicious receiver. Calls with calldata are forwarded with their
+/// value to the target in slot 0, so the receiver can act as the treasurer. On a
+/// plain transfer it calls the target once more with the payload in slots 1-2
+/// (length in slot 3) and the value in slot 4, and records in `RESULT_SLOT` whether
+/// that reentrant call reverted (1) or went through (2).
+const RECEIVER_RUNTIME: &str = "3660345760055460325760036005556001546000526002546020526000600060035460006004546000545af16001016005555b005b36600060003760006000366000346000545af13d600060003e6055573d6000fd5b3d6000f3";
+
+const RESULT_SLOT: u64 = 5;
+const REENTRY_REVERTED: u64 = 1;
+
+#[tokio::test]
+#[ignore = "needs a node with a deployed consumer, see the module docs"]
+async fn treasurer_cannot_reenter_withdraw_native() -> eyre::Result<()> {
+    let provider = Provider::<Http>::try_from(env("RPC_URL")?)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let wallet = LocalWallet::from_str(&env("PRIV_KEY")?)?.with_chain_id(chain_id);
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+
+    let address: Address = env("CONSUMER_ADDRESS")?.parse()?;
+    let consumer = DirectFundingConsumer::new(address, client.clone());
+
+    // Fund two withdrawals, so a reentrant one would find the surplus it needs
+    let amount = parse_ether("0.001")?;
+    let funding = TransactionRequest::new().to(address).value(amount * 2);
+    client.send_transaction(funding, None).await?.await?;
+
+    let payload = consumer.withdraw_native(amount).calldata().unwrap();
+    let receiver = deploy_receiver(&client, address, &payload, U256::zero()).await?;
+    let treasurer_role = consumer.treasurer_role().call().await?;
+    consumer
+        .grant_role(treasurer_role, receiver)
+        .send()
+        .await?
+        .await?;
+
+    let balance = client.get_balance(address, None).await?;
+    call_through(&client, receiver, payload, U256::zero()).await?;
+
+    assert_eq!(reentry_result(&client, receiver).await?, REENTRY_REVERTED);
+    assert_eq!(client.get_balance(address, None).await?, balance - amount);
+    assert_eq!(client.get_balance(receiver, None).await?, amount);
+
+    consumer
+        .revoke_role(treasurer_role, receiver)
+        .send()
+        .await?
+        .await?;
+
+    Ok(())
+}
+
+/// Deploys a receiver that forwards calls to `target` and, when paid, calls it back
+/// with `payload` and `value`
+async fn deploy_receiver(
+    client: &Arc<Client>,
+    target: Address,
+    payload: &[u8],
+    value: U256,
+) -> eyre::Result<Address> {
+    assert!(payload.len() <= 64, "payload must fit in two slots");
+    let mut padded = [0u8; 64];
+    padded[..payload.len()].copy_from_slice(payload);
+
+    let mut slots = [[0u8; 32]; 5];
+    slots[0][12..].copy_from_slice(target.as_bytes());
+    slots[1].copy_from_slice(&padded[..32]);
+    slots[2].copy_from_slice(&padded[32..]);
+    U256::from(payload.len()).to_big_endian(&mut slots[3]);
+    value.to_big_endian(&mut slots[4]);
+
+    // Constructor: PUSH32 word, PUSH1 slot, SSTORE for each slot, then return the
+    // runtime appended after it
+    let runtime = hex::decode(RECEIVER_RUNTIME)?;
+    let mut code = Vec::new();
+    for (slot, word) in slots.iter().enumerate() {
+        code.push(0x7f);
+        code.extend_from_slice(word);
+        code.extend_from_slice(&[0x60, slot as u8, 0x55]);
+    }
+    let offset = code.len() + 13;
+    let len = runtime.len();
+    code.extend_from_slice(&[0x61, (len >> 8) as u8, len as u8, 0x80]);
+    code.extend_from_slice(&[0x61, (offset >> 8) as u8, offset as u8]);
+    code.extend_from_slice(&[0x60, 0x00, 0x39, 0x60, 0x00, 0xf3]);
+    code.extend_from_slice(&runtime);
+
+    let receipt = client
+        .send_transaction(TransactionRequest::new().data(code), None)
+        .await?
+        .await?
+        .ok_or_else(|| eyre!("receiver deployment dropped"))?;
+    receipt
+        .contract_address
+        .ok_or_else(|| eyre!("receiver not deployed"))
+}
+
+async fn call_through(
+    client: &Arc<Client>,
+    receiver: Address,
+    calldata: Bytes,
+    value: U256,
+) -> eyre::Result<()> {
+    let tx = TransactionRequest::new()
+        .to(receiver)
+        .data(calldata)
+        .value(value);
+    let receipt = client
+        .send_transaction(tx, None)
+        .await?
+        .await?
+        .ok_or_else(|| eyre!("call dropped"))?;
+    if receipt.status != Some(1.into()) {
+        return Err(eyre!("call through the receiver reverted"));
+    }
+    Ok(())
+}
+
+async fn reentry_result(client: &Arc<Client>, receiver: Address) -> eyre::Result<u64> {
+    let slot = H256::from_low_u64_be(RESULT_SLOT);
+    let value = client.get_storage_at(receiver, slot, None).await?;
+    Ok(U256::from_big_endian(value.as_bytes()).as_u64())
+}
+
+fn env(name: &str) -> eyre::Result<String> {
+    std::env::var(name).map_err(|_| eyre!("{name} is not set"))
+}
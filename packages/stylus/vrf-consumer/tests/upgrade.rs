@@ -0,0 +1,96 @@
+//! End-to-end check that a proxied `DirectFundingConsumer` keeps its state across
+//! an upgrade.
+//!
+//! Needs a node (e.g. the nitro devnode) and these environment variables:
+//! - `RPC_URL`, `PRIV_KEY`: the node and the consumer owner's key
+//! - `CONSUMER_PROXY`: an `UpgradeableProxy` (`upgradeable-proxy`) deployed
+//!   with the `initialize(wrapper, owner)` call of a consumer implementation
+//! - `CONSUMER_NEXT_IMPL`: another deployment of the consumer implementation
+//!
+//! Upgrades are timelocked: the `upgradeToAndCall(CONSUMER_NEXT_IMPL, "")` operation must
+//! already be queued with `queueOperation` and past its ETA.
+//!
+//! Run with `cargo test --test upgrade -- --ignored`.
+
+use std::{str::FromStr, sync::Arc};
+
+use ethers::{
+    middleware::SignerMiddleware,
+    prelude::abigen,
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, TransactionRequest, U256},
+    utils::keccak256,
+};
+use eyre::eyre;
+
+abigen!(
+    DirectFundingConsumer,
+    r#"[
+        function setVrfBudgetReserve(uint256 reserve) external
+        function vrfBudgetReserve() external view returns (uint256)
+        function iVrfV2PlusWrapper() external view returns (address)
+        function getLastRequestId() external view returns (uint256)
+        function owner() external view returns (address)
+        function hasRole(bytes32 role, address account) external view returns (bool)
+        function upgradeTo(address newImplementation) external
+        function implementation() external view returns (address)
+    ]"#
+);
+
+#[tokio::test]
+#[ignore = "needs a node with a deployed proxy, see the module docs"]
+async fn state_survives_upgrade() -> eyre::Result<()> {
+    let provider = Provider::<Http>::try_from(env("RPC_URL")?)?;
+    let chain_id = provider.get_chainid().await?.as_u64();
+    let wallet = LocalWallet::from_str(&env("PRIV_KEY")?)?.with_chain_id(chain_id);
+    let owner = wallet.address();
+    let client = Arc::new(SignerMiddleware::new(provider, wallet));
+
+    let proxy: Address = env("CONSUMER_PROXY")?.parse()?;
+    let next_implementation: Address = env("CONSUMER_NEXT_IMPL")?.parse()?;
+    let consumer = DirectFundingConsumer::new(proxy, client.clone());
+
+    let reserve = U256::exp10(15);
+    consumer
+        .set_vrf_budget_reserve(reserve)
+        .send()
+        .await?
+        .await?;
+    client
+        .send_transaction(TransactionRequest::pay(proxy, reserve * 2), None)
+        .await?
+        .await?;
+
+    let wrapper = consumer.i_vrf_v2_plus_wrapper().call().await?;
+    let last_request_id = consumer.get_last_request_id().call().await?;
+    let balance = client.get_balance(proxy, None).await?;
+
+    consumer
+        .upgrade_to(next_implementation)
+        .send()
+        .await?
+        .await?;
+
+    assert_eq!(consumer.implementation().call().await?, next_implementation);
+    assert_eq!(consumer.owner().call().await?, owner);
+    assert!(
+        consumer
+            .has_role(keccak256("TREASURER_ROLE"), owner)
+            .call()
+            .await?
+    );
+    assert_eq!(consumer.vrf_budget_reserve().call().await?, reserve);
+    assert_eq!(consumer.i_vrf_v2_plus_wrapper().call().await?, wrapper);
+    assert_eq!(
+        consumer.get_last_request_id().call().await?,
+        last_request_id
+    );
+    assert_eq!(client.get_balance(proxy, None).await?, balance);
+
+    Ok(())
+}
+
+fn env(name: &str) -> eyre::Result<String> {
+    std::env::var(name).map_err(|_| eyre!("{name} is not set"))
+}